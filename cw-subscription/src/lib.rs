@@ -1,16 +0,0 @@
-#![feature(const_option, const_panic, assert_matches)]
-pub use crate::msg::{ExecuteMsg, InitMsg};
-pub use crate::query::{PlansResponse, QueryMsg, SubscriptionsResponse};
-pub use crate::state::{Plan, Subscription};
-
-pub mod bitset;
-pub mod contract;
-pub mod cron;
-#[cfg(not(target_arch = "wasm32"))]
-pub mod cron_spec;
-
-mod error;
-mod event;
-mod msg;
-mod query;
-mod state;
@@ -1,4 +1,3 @@
-use serde_json;
 use std::env;
 
 use cw_subscription::cron_spec::CronSpec;
@@ -1,34 +1,87 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    has_coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdResult, Storage, Uint128, WasmMsg,
+    has_coins, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    Order, Reply, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 use cw0::{Event, Expiration};
+use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
-use cw_storage_plus::U128Key;
+use cw_storage_plus::{Bound, U128Key, U64Key};
+use semver::Version;
 
+use crate::cw721::{Cw721ExecuteMsg, Cw721Extension, Cw721ExtensionMsg, Cw721MintMsg};
 use crate::error::ContractError;
 use crate::event::{
-    CreatePlanEvent, StopPlanEvent, SubscribeEvent, UnsubscribeEvent, UpdateSubscriptionEvent,
+    CollectionFailedEvent, CollectionLegFailedEvent, CreatePlanEvent, DepositBalanceEvent,
+    ReclaimContributionEvent, ReleasePlanEvent, StopPlanEvent, SubscribeEvent,
+    TransferSubscriptionEvent, UnsubscribeEvent, UpdateSubscriptionEvent, UpdateTierEvent,
+    WithdrawBalanceEvent,
 };
-use crate::msg::{CollectOne, ExecuteMsg, InitMsg, PlanContent};
-use crate::query::QueryMsg;
+use crate::msg::{AssetInfo, CollectOne, ExecuteMsg, InitMsg, MigrateMsg, PlanContent};
+use crate::query::{PlansResponse, QueryMsg, SubscriptionsResponse};
 use crate::state::{
-    gen_plan_id, iter_subscriptions_by_plan, Plan, Subscription, PARAMS, PLANS, Q_COLLECTION,
-    SUBSCRIPTIONS,
+    gen_plan_id, gen_reply_id, iter_collectible_subscriptions, iter_subscriptions_by_plan,
+    iter_subscriptions_of_user, migrate_legacy_plans, migrate_legacy_subscriptions, subscriptions,
+    Plan, ReplyContext, Subscription, NATIVE_ESCROW, PARAMS, PLANS, Q_COLLECTION,
+    REPLY_SUBSCRIPTION,
 };
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+const CONTRACT_NAME: &str = "crates.io:cw-subscription";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     msg: InitMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    msg.params.validate(deps.api)?;
     PARAMS.save(deps.storage, &msg.params)?;
     Ok(Response::default())
 }
 
+/// Upgrade entry point. A storage with no `cw2` version recorded at all
+/// predates this contract -- either a fresh pre-`cw2` deploy, or, since the
+/// two forks of this contract were consolidated into this one, a deploy of
+/// the predecessor `src/`-tree contract -- and is assumed to hold
+/// `plans`/`plan-subs` data in that contract's shape, which every such
+/// migration reshapes into the current schema. Downgrades, and migrations
+/// from an unrelated contract name, are rejected outright.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let new_version: Version = CONTRACT_VERSION.parse().unwrap();
+    let stored = cw2::CONTRACT.may_load(deps.storage)?;
+
+    let run_fixups = match stored {
+        None => true,
+        Some(ref v) if v.contract != CONTRACT_NAME => return Err(ContractError::InvalidMigration),
+        Some(v) => {
+            let stored_version: Version = v
+                .version
+                .parse()
+                .map_err(|_| ContractError::InvalidMigration)?;
+            if stored_version > new_version {
+                return Err(ContractError::InvalidMigration);
+            }
+            false
+        }
+    };
+
+    if run_fixups {
+        let now: i64 = env.block.time.seconds() as i64;
+        migrate_legacy_plans(deps.storage)?;
+        migrate_legacy_subscriptions(deps.storage, now)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::default())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -37,31 +90,72 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::CreatePlan(content) => execute_create_plan(deps, info, content),
+        ExecuteMsg::CreatePlan(content) => execute_create_plan(deps, env, info, content),
         ExecuteMsg::StopPlan { plan_id } => execute_stop_plan(deps, info, plan_id),
         ExecuteMsg::Subscribe {
             plan_id,
+            tier_id,
             expires,
-            next_collection_time,
-        } => execute_subscribe(deps, info, env, plan_id, expires, next_collection_time),
-        ExecuteMsg::Unsubscribe { plan_id } => execute_unsubscribe(deps, info, plan_id),
+        } => execute_subscribe(deps, info, env, plan_id, tier_id, expires),
+        ExecuteMsg::Unsubscribe { plan_id } => execute_unsubscribe(deps, env, info, plan_id),
         ExecuteMsg::UnsubscribeUser {
             plan_id,
             subscriber,
-        } => execute_unsubscribe_user(deps, info, plan_id, subscriber),
+        } => execute_unsubscribe_user(deps, env, info, plan_id, subscriber),
         ExecuteMsg::UpdateExpires { plan_id, expires } => {
             execute_update_expires(deps, env, info, plan_id, expires)
         }
-        ExecuteMsg::Collection { items } => execute_collection(deps, items),
+        ExecuteMsg::UpdateTier { plan_id, tier_id } => {
+            execute_update_tier(deps, env, info, plan_id, tier_id)
+        }
+        ExecuteMsg::Collection { items } => execute_collection(deps, env, items),
+        ExecuteMsg::ReleasePlan { plan_id } => execute_release_plan(deps, env, info, plan_id),
+        ExecuteMsg::ReclaimContribution { plan_id } => {
+            execute_reclaim_contribution(deps, env, info, plan_id)
+        }
+        ExecuteMsg::TransferSubscription { plan_id, recipient } => {
+            execute_transfer_subscription(deps, info, plan_id, recipient)
+        }
+        ExecuteMsg::DepositBalance { plan_id, amount } => {
+            execute_deposit_balance(deps, info, plan_id, amount)
+        }
+        ExecuteMsg::WithdrawBalance { plan_id, amount } => {
+            execute_withdraw_balance(deps, info, plan_id, amount)
+        }
     }
 }
 
+/// Build the message that pays `amount` of a plan's billing asset straight
+/// out to `recipient`, used by `ReleasePlan`/`ReclaimContribution` to sweep
+/// the crowdfunding pool regardless of whether it's denominated in a cw20 or
+/// a native token.
+fn payout_msg(asset: &AssetInfo<Addr>, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match asset {
+        AssetInfo::Cw20 { addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_owned(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+        AssetInfo::Native { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_owned(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }),
+    })
+}
+
 fn execute_create_plan(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    content: PlanContent,
+    content: PlanContent<String>,
 ) -> Result<Response, ContractError> {
-    content.validate()?;
+    let content = content.validate(deps.api, &env.block)?;
 
     let params = PARAMS.load(deps.storage)?;
     let id = gen_plan_id(deps.storage)?;
@@ -75,6 +169,7 @@ fn execute_create_plan(
         owner: info.sender,
         content,
         deposit: info.funds,
+        collected: Uint128::zero(),
     };
     PLANS.save(deps.storage, id.u128().into(), &plan)?;
 
@@ -96,30 +191,32 @@ fn execute_stop_plan(
     let mut rsp = Response::default();
 
     // Stop all subscriptions
-    let subscriptions: Vec<_> = iter_subscriptions_by_plan(deps.storage, plan_id).collect();
-    for (subscriber, sub) in subscriptions.into_iter() {
+    let subs: Vec<_> = iter_subscriptions_by_plan(deps.storage, plan_id, None).collect();
+    for (subscriber, sub) in subs.into_iter() {
         UnsubscribeEvent {
             plan_id,
             subscriber: subscriber.as_str(),
+            earned: &[],
+            refunded: &sub.deposit,
         }
         .add_attributes(&mut rsp);
 
         let key = (plan_id.u128().into(), subscriber.as_str());
-        SUBSCRIPTIONS.remove(deps.storage, key.clone());
+        subscriptions().remove(deps.storage, key.clone())?;
         // delete in queue
         Q_COLLECTION.remove(deps.storage, (sub.next_collection_time.into(), key));
-        rsp.messages.push(CosmosMsg::Bank(BankMsg::Send {
+        rsp.messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
             to_address: subscriber.into(),
             amount: sub.deposit,
-        }));
+        })));
     }
 
     // Delete plan
     PLANS.remove(deps.storage, plan_id.u128().into());
-    rsp.messages.push(CosmosMsg::Bank(BankMsg::Send {
+    rsp.messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
         to_address: plan.owner.into(),
         amount: plan.deposit,
-    }));
+    })));
     StopPlanEvent { plan_id: plan.id }.add_attributes(&mut rsp);
     Ok(rsp)
 }
@@ -129,8 +226,8 @@ fn execute_subscribe(
     info: MessageInfo,
     env: Env,
     plan_id: Uint128,
+    tier_id: u64,
     expires: Expiration,
-    next_collection_time: i64,
 ) -> Result<Response, ContractError> {
     // verify expires is valid
     if expires.is_expired(&env.block) {
@@ -139,8 +236,7 @@ fn execute_subscribe(
 
     // verify subscription not exists
     let key = (plan_id.u128().into(), info.sender.as_str());
-    let subkey = SUBSCRIPTIONS.key(key.clone());
-    if deps.storage.get(&subkey).is_some() {
+    if subscriptions().may_load(deps.storage, key.clone())?.is_some() {
         return Err(ContractError::SubscriptionExists);
     }
 
@@ -152,21 +248,58 @@ fn execute_subscribe(
         }
     }
 
-    // verify next_collection_time
+    // derive next_collection_time from the plan's cron schedule ourselves,
+    // instead of trusting a client-supplied value
     let plan = PLANS.load(deps.storage, plan_id.u128().into())?;
-    plan.content.verify_timestamp(next_collection_time);
+    plan.content
+        .tier_amount(tier_id)
+        .ok_or(ContractError::UnknownTier)?;
+    let now: i64 = env.block.time.seconds() as i64;
+    let next_collection_time = plan
+        .content
+        .next_collection_time(now)
+        .ok_or(ContractError::InvalidCollectionTime)?;
+
+    // mint a companion NFT for this subscription if the contract is
+    // configured for it, so the subscription itself becomes a transferable,
+    // self-expiring token tradable through the collection's own cw721
+    // interface. It's minted straight to the subscriber, who stays the real
+    // owner of record; for this contract's later `TransferNft`/
+    // `UpdateExpiration` calls (on `TransferSubscription`/`UpdateExpires`)
+    // to succeed, the subscriber must separately grant this contract an
+    // `Approve`/`ApproveAll` on the collection at subscribe time.
+    let nft_token_id = params
+        .nft_collection
+        .as_ref()
+        .map(|_| format!("{}-{}", plan_id, info.sender));
 
     // insert new subscription
     let sub = Subscription {
         expires,
-        last_collection_time: None,
+        tier_id,
+        last_collection_time: now,
         next_collection_time,
         deposit: info.funds,
+        consecutive_failures: 0,
+        contributed: Uint128::zero(),
+        nft_token_id: nft_token_id.clone(),
     };
-    subkey.save(deps.storage, &sub)?;
+    subscriptions().save(deps.storage, key.clone(), &sub)?;
     Q_COLLECTION.save(deps.storage, (next_collection_time.into(), key), &())?;
 
     let mut rsp = Response::default();
+    if let (Some(nft_collection), Some(token_id)) = (&params.nft_collection, nft_token_id) {
+        rsp.messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: nft_collection.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::Mint(Cw721MintMsg {
+                token_id,
+                owner: info.sender.to_string(),
+                token_uri: None,
+                extension: Cw721Extension { expiration: expires },
+            }))?,
+            funds: vec![],
+        })));
+    }
     SubscribeEvent {
         plan_id,
         subscriber: info.sender.as_str(),
@@ -175,42 +308,134 @@ fn execute_subscribe(
     Ok(rsp)
 }
 
+/// The per-coin split an unsubscribe produced: `earned` is credited to the
+/// plan owner for service already rendered this period, `refunded` is
+/// returned to the subscriber.
+struct UnsubscribeSplit {
+    earned: Vec<Coin>,
+    refunded: Vec<Coin>,
+}
+
+/// Remove a subscription and split its deposit between the plan owner and
+/// the subscriber. When `prorate` is set and the subscription has already
+/// been collected at least once, the split reflects the fraction of the
+/// current billing interval elapsed by `now`; otherwise the whole deposit is
+/// refunded, matching the historical full-refund behavior.
 fn unsubscribe(
     storage: &mut dyn Storage,
     plan_id: Uint128,
     subscriber: Addr,
-) -> StdResult<CosmosMsg> {
+    now: i64,
+    prorate: bool,
+) -> StdResult<(UnsubscribeSplit, Vec<CosmosMsg>)> {
     // delete subscription
     let key = (U128Key::from(plan_id.u128()), subscriber.as_str());
-    let sub = SUBSCRIPTIONS.load(storage, key.clone())?;
-    SUBSCRIPTIONS.remove(storage, key.clone());
+    let sub = subscriptions().load(storage, key.clone())?;
+    subscriptions().remove(storage, key.clone())?;
     // delete in queue
-    Q_COLLECTION.remove(storage, (sub.next_collection_time.into(), key));
-    Ok(CosmosMsg::Bank(BankMsg::Send {
-        to_address: subscriber.into(),
-        amount: sub.deposit,
-    }))
+    Q_COLLECTION.remove(storage, (sub.next_collection_time.into(), key.clone()));
+
+    // (elapsed, period) of the current billing interval, or `None` if
+    // nothing should be prorated
+    let period = sub.next_collection_time - sub.last_collection_time;
+    let fraction = if prorate && period > 0 {
+        let elapsed = (now - sub.last_collection_time).clamp(0, period);
+        Some((elapsed as u128, period as u128))
+    } else {
+        None
+    };
+
+    let mut earned = Vec::new();
+    let mut refunded = Vec::new();
+    for coin in sub.deposit.iter() {
+        // multiply before divide, flooring the result; any rounding dust
+        // stays with the subscriber's refund share
+        let earned_amount = match fraction {
+            Some((elapsed, period)) => Uint128::from(coin.amount.u128() * elapsed / period),
+            None => Uint128::zero(),
+        };
+        let refund_amount = coin.amount - earned_amount;
+        if !earned_amount.is_zero() {
+            earned.push(Coin {
+                denom: coin.denom.clone(),
+                amount: earned_amount,
+            });
+        }
+        if !refund_amount.is_zero() {
+            refunded.push(Coin {
+                denom: coin.denom.clone(),
+                amount: refund_amount,
+            });
+        }
+    }
+
+    let plan = PLANS.load(storage, plan_id.u128().into())?;
+    let mut msgs = Vec::new();
+    if !earned.is_empty() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: plan.owner.to_string(),
+            amount: earned.clone(),
+        }));
+    }
+    if !refunded.is_empty() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: subscriber.to_string(),
+            amount: refunded.clone(),
+        }));
+    }
+
+    // refund whatever's left of the subscriber's prepaid native escrow too,
+    // so it doesn't get stranded in the contract once there's no longer any
+    // subscription left to debit it from
+    if let Some(balance) = NATIVE_ESCROW.may_load(storage, key.clone())? {
+        NATIVE_ESCROW.remove(storage, key);
+        if !balance.is_zero() {
+            if let AssetInfo::Native { denom } = &plan.content.asset {
+                msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: subscriber.into(),
+                    amount: vec![Coin {
+                        denom: denom.clone(),
+                        amount: balance,
+                    }],
+                }));
+            }
+        }
+    }
+
+    Ok((UnsubscribeSplit { earned, refunded }, msgs))
 }
 
 fn execute_unsubscribe(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     plan_id: Uint128,
 ) -> Result<Response, ContractError> {
+    let params = PARAMS.load(deps.storage)?;
+    let now: i64 = env.block.time.seconds() as i64;
+
     let mut rsp = Response::default();
+    let (split, refund_msgs) = unsubscribe(
+        deps.storage,
+        plan_id,
+        info.sender.clone(),
+        now,
+        params.prorate_on_cancel,
+    )?;
+    rsp.messages.extend(refund_msgs.into_iter().map(SubMsg::new));
     UnsubscribeEvent {
         plan_id,
         subscriber: info.sender.as_str(),
+        earned: &split.earned,
+        refunded: &split.refunded,
     }
     .add_attributes(&mut rsp);
-
-    let refund_msg = unsubscribe(deps.storage, plan_id, info.sender)?;
-    rsp.messages.push(refund_msg);
     Ok(rsp)
 }
 
 fn execute_unsubscribe_user(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     plan_id: Uint128,
     subscriber: String,
@@ -222,15 +447,25 @@ fn execute_unsubscribe_user(
         return Err(ContractError::NotPlanOwner);
     }
 
+    let params = PARAMS.load(deps.storage)?;
+    let now: i64 = env.block.time.seconds() as i64;
+
     let mut rsp = Response::default();
+    let (split, refund_msgs) = unsubscribe(
+        deps.storage,
+        plan_id,
+        subscriber.clone(),
+        now,
+        params.prorate_on_cancel,
+    )?;
+    rsp.messages.extend(refund_msgs.into_iter().map(SubMsg::new));
     UnsubscribeEvent {
         plan_id,
         subscriber: subscriber.as_str(),
+        earned: &split.earned,
+        refunded: &split.refunded,
     }
     .add_attributes(&mut rsp);
-
-    let refund_msg = unsubscribe(deps.storage, plan_id, subscriber)?;
-    rsp.messages.push(refund_msg);
     Ok(rsp)
 }
 
@@ -244,12 +479,28 @@ fn execute_update_expires(
     if expires.is_expired(&env.block) {
         return Err(ContractError::InvalidExpires);
     }
-    let key = SUBSCRIPTIONS.key((plan_id.u128().into(), info.sender.as_str()));
-    let mut subscription = key.load(deps.storage)?;
+    let key = (plan_id.u128().into(), info.sender.as_str());
+    let mut subscription = subscriptions().load(deps.storage, key.clone())?;
     subscription.expires = expires;
-    key.save(deps.storage, &subscription)?;
+    let nft_token_id = subscription.nft_token_id.clone();
+    subscriptions().save(deps.storage, key, &subscription)?;
 
     let mut rsp = Response::default();
+    if let Some(token_id) = nft_token_id {
+        let params = PARAMS.load(deps.storage)?;
+        if let Some(nft_collection) = &params.nft_collection {
+            rsp.messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: nft_collection.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::Extension {
+                    msg: Cw721ExtensionMsg::UpdateExpiration {
+                        token_id,
+                        expiration: expires,
+                    },
+                })?,
+                funds: vec![],
+            })));
+        }
+    }
     UpdateSubscriptionEvent {
         plan_id,
         subscriber: info.sender.as_str(),
@@ -258,64 +509,1264 @@ fn execute_update_expires(
     Ok(rsp)
 }
 
-fn execute_collection(deps: DepsMut, items: Vec<CollectOne>) -> Result<Response, ContractError> {
+/// Switch the sender's subscription to a different tier of the same plan,
+/// prorating the remainder of the current period by the ratio of the old
+/// tier's price to the new one instead of charging or refunding money
+/// mid-period: a cheaper tier stretches the time left, a pricier one
+/// shrinks it.
+fn execute_update_tier(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    plan_id: Uint128,
+    tier_id: u64,
+) -> Result<Response, ContractError> {
+    let plan = PLANS.load(deps.storage, plan_id.u128().into())?;
+    let old_key = (plan_id.u128().into(), info.sender.as_str());
+    let mut subscription = subscriptions().load(deps.storage, old_key.clone())?;
+
+    let old_amount = plan
+        .content
+        .tier_amount(subscription.tier_id)
+        .ok_or(ContractError::UnknownTier)?;
+    let new_amount = plan
+        .content
+        .tier_amount(tier_id)
+        .ok_or(ContractError::UnknownTier)?;
+
+    let now: i64 = env.block.time.seconds() as i64;
+    let remaining = subscription.next_collection_time.saturating_sub(now).max(0) as u128;
+    // multiply before divide, flooring the result, same as the proration
+    // math used elsewhere for a partial-period refund split
+    let new_remaining = (remaining * old_amount.u128() / new_amount.u128()) as i64;
+
+    Q_COLLECTION.remove(
+        deps.storage,
+        (subscription.next_collection_time.into(), old_key.clone()),
+    );
+    subscription.tier_id = tier_id;
+    subscription.next_collection_time = now + new_remaining;
+    Q_COLLECTION.save(
+        deps.storage,
+        (subscription.next_collection_time.into(), old_key.clone()),
+        &(),
+    )?;
+    subscriptions().save(deps.storage, old_key, &subscription)?;
+
     let mut rsp = Response::default();
-    for item in items.iter() {
-        if item.next_collection_time <= item.current_collection_time {
-            // TODO handle failure
-            continue;
+    UpdateTierEvent {
+        plan_id,
+        subscriber: info.sender.as_str(),
+        tier_id,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+fn execute_release_plan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    plan_id: Uint128,
+) -> Result<Response, ContractError> {
+    let mut plan = PLANS.load(deps.storage, plan_id.u128().into())?;
+    if plan.owner != info.sender {
+        return Err(ContractError::NotPlanOwner);
+    }
+    let goal = plan.content.goal.ok_or(ContractError::NotCrowdfundingPlan)?;
+    if !plan.content.campaign_closed(&env.block) {
+        return Err(ContractError::CampaignNotClosed);
+    }
+    if plan.collected < goal {
+        return Err(ContractError::GoalNotMet);
+    }
+
+    let amount = plan.collected;
+    plan.collected = Uint128::zero();
+    let payout = payout_msg(&plan.content.asset, plan.owner.as_str(), amount)?;
+    PLANS.save(deps.storage, plan_id.u128().into(), &plan)?;
+
+    let mut rsp = Response::default();
+    rsp.messages.push(SubMsg::new(payout));
+    ReleasePlanEvent { plan_id, amount }.add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+fn execute_reclaim_contribution(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    plan_id: Uint128,
+) -> Result<Response, ContractError> {
+    let mut plan = PLANS.load(deps.storage, plan_id.u128().into())?;
+    let goal = plan.content.goal.ok_or(ContractError::NotCrowdfundingPlan)?;
+    if !plan.content.campaign_closed(&env.block) {
+        return Err(ContractError::CampaignNotClosed);
+    }
+    if plan.collected >= goal {
+        return Err(ContractError::GoalAlreadyMet);
+    }
+
+    let key = (plan_id.u128().into(), info.sender.as_str());
+    let mut subscription = subscriptions().load(deps.storage, key.clone())?;
+    if subscription.contributed.is_zero() {
+        return Err(ContractError::NothingToReclaim);
+    }
+
+    let amount = subscription.contributed;
+    subscription.contributed = Uint128::zero();
+    subscriptions().save(deps.storage, key, &subscription)?;
+
+    plan.collected -= amount;
+    let payout = payout_msg(&plan.content.asset, info.sender.as_str(), amount)?;
+    PLANS.save(deps.storage, plan_id.u128().into(), &plan)?;
+
+    let mut rsp = Response::default();
+    rsp.messages.push(SubMsg::new(payout));
+    ReclaimContributionEvent {
+        plan_id,
+        subscriber: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+fn execute_transfer_subscription(
+    deps: DepsMut,
+    info: MessageInfo,
+    plan_id: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let old_key = (plan_id.u128().into(), info.sender.as_str());
+    let subscription = subscriptions().load(deps.storage, old_key.clone())?;
+
+    let new_key = (plan_id.u128().into(), recipient.as_str());
+    if subscriptions()
+        .may_load(deps.storage, new_key.clone())?
+        .is_some()
+    {
+        return Err(ContractError::SubscriptionExists);
+    }
+
+    subscriptions().remove(deps.storage, old_key.clone())?;
+    Q_COLLECTION.remove(
+        deps.storage,
+        (subscription.next_collection_time.into(), old_key),
+    );
+    subscriptions().save(deps.storage, new_key.clone(), &subscription)?;
+    Q_COLLECTION.save(
+        deps.storage,
+        (subscription.next_collection_time.into(), new_key),
+        &(),
+    )?;
+
+    let mut rsp = Response::default();
+    // the companion NFT, if any, is owned by the sender, not this contract,
+    // so reassign it to the recipient alongside the internal subscriber key
+    // -- this requires the sender to have granted this contract an
+    // Approve/ApproveAll on the collection, the same grant UpdateExpires
+    // already relies on
+    if let Some(token_id) = &subscription.nft_token_id {
+        let params = PARAMS.load(deps.storage)?;
+        if let Some(nft_collection) = &params.nft_collection {
+            rsp.messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: nft_collection.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token_id.clone(),
+                })?,
+                funds: vec![],
+            })));
         }
+    }
+    TransferSubscriptionEvent {
+        plan_id,
+        from: info.sender.as_str(),
+        to: recipient.as_str(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+fn execute_deposit_balance(
+    deps: DepsMut,
+    info: MessageInfo,
+    plan_id: Uint128,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let plan = PLANS.load(deps.storage, plan_id.u128().into())?;
+    let denom = match &plan.content.asset {
+        AssetInfo::Native { denom } => denom.clone(),
+        AssetInfo::Cw20 { .. } => return Err(ContractError::EscrowNotSupported),
+    };
+    if !has_coins(&info.funds, &Coin { denom, amount }) {
+        return Err(ContractError::NotEnoughDeposit);
+    }
+
+    let key = (plan_id.u128().into(), info.sender.as_str());
+    let balance = NATIVE_ESCROW
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    NATIVE_ESCROW.save(deps.storage, key, &(balance + amount))?;
+
+    let mut rsp = Response::default();
+    DepositBalanceEvent {
+        plan_id,
+        subscriber: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+fn execute_withdraw_balance(
+    deps: DepsMut,
+    info: MessageInfo,
+    plan_id: Uint128,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let plan = PLANS.load(deps.storage, plan_id.u128().into())?;
+    let denom = match &plan.content.asset {
+        AssetInfo::Native { denom } => denom.clone(),
+        AssetInfo::Cw20 { .. } => return Err(ContractError::EscrowNotSupported),
+    };
+
+    let key = (plan_id.u128().into(), info.sender.as_str());
+    let balance = NATIVE_ESCROW
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    let remaining = balance
+        .checked_sub(amount)
+        .map_err(|_| ContractError::NotEnoughDeposit)?;
+    if remaining.is_zero() {
+        NATIVE_ESCROW.remove(deps.storage, key);
+    } else {
+        NATIVE_ESCROW.save(deps.storage, key, &remaining)?;
+    }
+
+    let mut rsp = Response::default();
+    rsp.messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin { denom, amount }],
+    })));
+    WithdrawBalanceEvent {
+        plan_id,
+        subscriber: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+fn execute_collection(
+    deps: DepsMut,
+    env: Env,
+    items: Vec<CollectOne>,
+) -> Result<Response, ContractError> {
+    let mut rsp = Response::default();
+    for item in items.iter() {
         let subscriber = deps.api.addr_validate(&item.subscriber)?;
 
         // load plan and subscription
         let plan = PLANS.load(deps.storage, item.plan_id.u128().into())?;
         let key = (item.plan_id.u128().into(), subscriber.as_str());
-        let mut subscription = SUBSCRIPTIONS.load(deps.storage, key.clone())?;
-        if let Some(last_collection_time) = subscription.last_collection_time {
-            if item.current_collection_time <= last_collection_time {
+        let mut subscription = subscriptions().load(deps.storage, key.clone())?;
+        if item.current_collection_time <= subscription.last_collection_time {
+            // TODO handle failure
+            continue;
+        }
+        // verify collection time matches cron spec, then derive the next
+        // one ourselves instead of trusting a client-supplied value
+        if !plan.content.verify_timestamp(item.current_collection_time) {
+            // TODO handle failure
+            continue;
+        }
+        let next_collection_time = match plan.content.next_collection_time(item.current_collection_time)
+        {
+            Some(t) => t,
+            // TODO handle failure
+            None => continue,
+        };
+
+        // `Collection` is permissionless, so the requested tier must match
+        // the subscriber's own `tier_id` -- otherwise anyone could submit a
+        // pricier tier than the subscriber actually subscribed to and
+        // over-bill them. A mismatch, or a tier the plan no longer offers,
+        // fails this leg rather than the whole collection.
+        let tier_total = if item.tier_id != subscription.tier_id {
+            CollectionLegFailedEvent {
+                plan_id: item.plan_id,
+                subscriber: subscriber.as_str(),
+                tier_id: item.tier_id,
+            }
+            .add_attributes(&mut rsp);
+            Uint128::zero()
+        } else {
+            match plan.content.tier_amount(item.tier_id) {
+                Some(amount) => amount,
+                None => {
+                    CollectionLegFailedEvent {
+                        plan_id: item.plan_id,
+                        subscriber: subscriber.as_str(),
+                        tier_id: item.tier_id,
+                    }
+                    .add_attributes(&mut rsp);
+                    Uint128::zero()
+                }
+            }
+        };
+        // the billing mode turns that tier total (or, for a metered plan,
+        // the reported usage) into what's actually billed this period
+        let total = match plan.content.billed_amount(
+            tier_total,
+            item.usage_units,
+            subscription.last_collection_time,
+            item.current_collection_time,
+            next_collection_time,
+        ) {
+            Ok(total) => total,
+            Err(_) => {
                 // TODO handle failure
                 continue;
             }
-        }
-        // verify collection time match cron spec
-        if !plan.content.verify_timestamp(item.current_collection_time)
-            || !plan.content.verify_timestamp(item.next_collection_time)
-        {
+        };
+        if total.is_zero() {
             // TODO handle failure
             continue;
         }
 
-        // do cw20 transfer
-        // TODO handle transfer failure with submessage callback
-        rsp.messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: plan.content.token.into(),
-            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
-                owner: subscriber.clone().into(),
-                recipient: plan.owner.into(),
-                amount: plan.content.amount,
-            })?,
-            send: vec![],
-        }));
+        // crowdfunding plans pool every collection in the contract itself
+        // instead of forwarding it straight to the owner, until `ReleasePlan`
+        // sweeps the pool once `goal` is reached
+        let recipient = if plan.content.goal.is_some() {
+            env.contract.address.clone()
+        } else {
+            plan.owner.clone()
+        };
 
-        // update next_collection_time
-        subscription.last_collection_time = Some(item.current_collection_time);
-        Q_COLLECTION.remove(
-            deps.storage,
-            (subscription.next_collection_time.into(), key.clone()),
-        );
-        subscription.next_collection_time = item.next_collection_time;
-        Q_COLLECTION.save(
-            deps.storage,
-            (subscription.next_collection_time.into(), key.clone()),
-            &(),
-        )?;
-        SUBSCRIPTIONS.save(deps.storage, key, &subscription)?;
+        match &plan.content.asset {
+            AssetInfo::Cw20 { addr } => {
+                // optimistically advance next_collection_time; if the
+                // transfer below fails, `reply` rolls this back using the
+                // prior values stashed alongside the reply id
+                let prior_last_collection_time = subscription.last_collection_time;
+                let prior_next_collection_time = subscription.next_collection_time;
+                subscription.last_collection_time = item.current_collection_time;
+                Q_COLLECTION.remove(
+                    deps.storage,
+                    (subscription.next_collection_time.into(), key.clone()),
+                );
+                subscription.next_collection_time = next_collection_time;
+                Q_COLLECTION.save(
+                    deps.storage,
+                    (subscription.next_collection_time.into(), key.clone()),
+                    &(),
+                )?;
+                subscriptions().save(deps.storage, key.clone(), &subscription)?;
+
+                let reply_id = gen_reply_id(
+                    deps.storage,
+                    ReplyContext {
+                        plan_id: item.plan_id,
+                        subscriber: subscriber.to_string(),
+                        prior_last_collection_time,
+                        prior_next_collection_time,
+                        amount: total,
+                    },
+                )?;
+                rsp.messages.push(SubMsg::reply_on_error(
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: addr.to_string(),
+                        msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                            owner: subscriber.clone().into(),
+                            recipient: recipient.into(),
+                            amount: total,
+                        })?,
+                        funds: vec![],
+                    }),
+                    reply_id,
+                ));
+            }
+            AssetInfo::Native { denom } => {
+                // native tokens can't be pulled via allowance, so this draws
+                // down the subscriber's prepaid escrow instead; since that
+                // can't fail asynchronously, there's no reply to wait on and
+                // the advance below is final immediately
+                let balance = NATIVE_ESCROW
+                    .may_load(deps.storage, key.clone())?
+                    .unwrap_or_default();
+                if balance < total {
+                    // TODO handle failure
+                    continue;
+                }
+                NATIVE_ESCROW.save(deps.storage, key.clone(), &(balance - total))?;
+
+                subscription.last_collection_time = item.current_collection_time;
+                Q_COLLECTION.remove(
+                    deps.storage,
+                    (subscription.next_collection_time.into(), key.clone()),
+                );
+                subscription.next_collection_time = next_collection_time;
+                Q_COLLECTION.save(
+                    deps.storage,
+                    (subscription.next_collection_time.into(), key.clone()),
+                    &(),
+                )?;
+                subscription.consecutive_failures = 0;
+                if plan.content.goal.is_some() {
+                    subscription.contributed += total;
+                    let mut plan = plan.clone();
+                    plan.collected += total;
+                    PLANS.save(deps.storage, item.plan_id.u128().into(), &plan)?;
+                }
+                subscriptions().save(deps.storage, key.clone(), &subscription)?;
+
+                rsp.messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: vec![Coin {
+                        denom: denom.clone(),
+                        amount: total,
+                    }],
+                })));
+            }
+        }
+    }
+
+    Ok(rsp)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let ctx = REPLY_SUBSCRIPTION
+        .may_load(deps.storage, U64Key::from(msg.id))?
+        .ok_or(ContractError::UnknownReplyId)?;
+    REPLY_SUBSCRIPTION.remove(deps.storage, U64Key::from(msg.id));
+
+    let key = (ctx.plan_id.u128().into(), ctx.subscriber.as_str());
+    let mut subscription = subscriptions().load(deps.storage, key.clone())?;
+
+    if msg.result.is_ok() {
+        // the transfer went through: keep the optimistic advance, just
+        // reset the streak of failures
+        subscription.consecutive_failures = 0;
+
+        // for crowdfunding plans, the transfer landed in the contract's
+        // pool rather than the owner's wallet; credit it now that it's
+        // confirmed instead of optimistically, since a failed transfer
+        // never touches the pool
+        let mut plan = PLANS.load(deps.storage, ctx.plan_id.u128().into())?;
+        if plan.content.goal.is_some() {
+            subscription.contributed += ctx.amount;
+            plan.collected += ctx.amount;
+            PLANS.save(deps.storage, ctx.plan_id.u128().into(), &plan)?;
+        }
+
+        subscriptions().save(deps.storage, key, &subscription)?;
+        return Ok(Response::default());
     }
 
+    // the transfer failed: this collection never happened, so roll the
+    // subscription back to where it was before `execute_collection` ran
+    Q_COLLECTION.remove(
+        deps.storage,
+        (subscription.next_collection_time.into(), key.clone()),
+    );
+    subscription.last_collection_time = ctx.prior_last_collection_time;
+    subscription.next_collection_time = ctx.prior_next_collection_time;
+    subscription.consecutive_failures += 1;
+    Q_COLLECTION.save(
+        deps.storage,
+        (subscription.next_collection_time.into(), key.clone()),
+        &(),
+    )?;
+    subscriptions().save(deps.storage, key, &subscription)?;
+
+    let mut rsp = Response::default();
+    let params = PARAMS.load(deps.storage)?;
+    // a crowdfunding subscriber's `contributed` total only lives on their
+    // Subscription row; auto-unsubscribing while the campaign hasn't
+    // resolved yet (goal not reached, so `ReleasePlan` hasn't swept it)
+    // would delete that row and leave `ReclaimContribution` with nothing to
+    // load, stranding the funds for good. Hold off until the campaign
+    // resolves one way or the other.
+    let plan = PLANS.load(deps.storage, ctx.plan_id.u128().into())?;
+    let campaign_unresolved = matches!(plan.content.goal, Some(goal) if plan.collected < goal);
+    let unsubscribed = match params.max_consecutive_failures {
+        Some(max)
+            if subscription.consecutive_failures >= max
+                && (!campaign_unresolved || subscription.contributed.is_zero()) =>
+        {
+            let now: i64 = env.block.time.seconds() as i64;
+            // auto-unsubscribe is a penalty, not a subscriber-initiated
+            // cancel, so it always refunds the full deposit regardless of
+            // Params.prorate_on_cancel
+            let (_, refund_msgs) = unsubscribe(
+                deps.storage,
+                ctx.plan_id,
+                Addr::unchecked(ctx.subscriber.clone()),
+                now,
+                false,
+            )?;
+            rsp.messages.extend(refund_msgs.into_iter().map(SubMsg::new));
+            true
+        }
+        _ => false,
+    };
+    CollectionFailedEvent {
+        plan_id: ctx.plan_id,
+        subscriber: &ctx.subscriber,
+        consecutive_failures: subscription.consecutive_failures,
+        unsubscribed,
+    }
+    .add_attributes(&mut rsp);
     Ok(rsp)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    Ok(Binary::default())
+    match msg {
+        QueryMsg::Plan { plan_id } => to_binary(&PLANS.load(deps.storage, plan_id.u128().into())?),
+        QueryMsg::Subscription {
+            plan_id,
+            subscriber,
+        } => to_binary(&subscriptions().load(deps.storage, (plan_id.u128().into(), &subscriber))?),
+        QueryMsg::ListSubscriptions {
+            plan_id,
+            start_after,
+            limit,
+        } => query_subscriptions(deps, plan_id, start_after, limit),
+        QueryMsg::ListSubscriptionsOfUser {
+            subscriber,
+            start_after,
+            limit,
+        } => query_subscriptions_of_user(deps, subscriber, start_after, limit),
+        QueryMsg::ListPlans { start_after, limit } => query_plans(deps, start_after, limit),
+        QueryMsg::CollectibleSubscriptions { limit } => {
+            query_collectible_subscriptions(deps, env, limit)
+        }
+    }
+}
+
+fn query_plans(deps: Deps, start_after: Option<Uint128>, limit: Option<u32>) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|id| Bound::Exclusive(U128Key::from(id.u128()).into()));
+    let plans = PLANS
+        .range(deps.storage, start_after, None, Order::Ascending)
+        .map(|mpair| mpair.unwrap().1)
+        .take(limit)
+        .collect();
+    to_binary(&PlansResponse { plans })
+}
+
+fn query_subscriptions(
+    deps: Deps,
+    plan_id: Uint128,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let subscriptions = iter_subscriptions_by_plan(deps.storage, plan_id, start_after)
+        .map(|(subscriber, sub)| (plan_id, subscriber, sub))
+        .take(limit)
+        .collect();
+    to_binary(&SubscriptionsResponse { subscriptions })
+}
+
+fn query_subscriptions_of_user(
+    deps: Deps,
+    subscriber: String,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let subscriber = deps.api.addr_validate(&subscriber)?;
+    let subscriptions = iter_subscriptions_of_user(deps.storage, subscriber.clone(), start_after)
+        .map(|(plan_id, sub)| (plan_id, subscriber.clone(), sub))
+        .take(limit)
+        .collect();
+    to_binary(&SubscriptionsResponse { subscriptions })
+}
+
+fn query_collectible_subscriptions(deps: Deps, env: Env, limit: Option<u32>) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let items: Vec<_> =
+        iter_collectible_subscriptions(deps.storage, env.block.time.seconds() as i64)
+            .take(limit)
+            .collect();
+    let subscriptions = items
+        .into_iter()
+        .map(|(_, plan_id, subscriber)| {
+            subscriptions()
+                .load(deps.storage, (plan_id.u128().into(), subscriber.as_str()))
+                .map(|sub| (plan_id, subscriber, sub))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&SubscriptionsResponse { subscriptions })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, Addr, Timestamp};
+
+    use super::*;
+
+    use crate::cron_spec::CronSpec;
+    use crate::msg::{BillingMode, Params, Tier};
+
+    type TestDeps = cosmwasm_std::OwnedDeps<
+        cosmwasm_std::testing::MockStorage,
+        cosmwasm_std::testing::MockApi,
+        cosmwasm_std::testing::MockQuerier,
+    >;
+
+    /// Run a `Collection` for one subscriber and feed the resulting
+    /// submessage's reply back in as a success, the way a real chain would.
+    fn collect_and_confirm(
+        deps: &mut TestDeps,
+        env: &Env,
+        plan_id: Uint128,
+        subscriber: &str,
+        current_collection_time: i64,
+    ) {
+        let collect_rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(subscriber, &[]),
+            ExecuteMsg::Collection {
+                items: vec![CollectOne {
+                    plan_id,
+                    subscriber: subscriber.to_owned(),
+                    current_collection_time,
+                    tier_id: 0,
+                    usage_units: None,
+                }],
+            },
+        )
+        .unwrap();
+        let reply_id = collect_rsp.messages[0].id;
+        reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: reply_id,
+                result: cosmwasm_std::ContractResult::Ok(cosmwasm_std::SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn check_basic_flow() {
+        // instantiate a contract
+        // create plan
+        // subscribe
+        // collect payment
+        // query
+        let _native_token = "cro".to_owned();
+        let merchant = String::from("merchant");
+        let user = String::from("user");
+        let token_contract = String::from("cw20-contract");
+
+        let mut deps = mock_dependencies(&[]);
+        let msg = InitMsg {
+            params: Params {
+                required_deposit_plan: vec![],
+                required_deposit_subscription: vec![],
+                max_consecutive_failures: None,
+                prorate_on_cancel: false,
+                nft_collection: None,
+            },
+        };
+        let env = mock_env();
+
+        let res = instantiate(deps.as_mut(), env.clone(), mock_info("operator", &[]), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let content = PlanContent::<String> {
+            title: "test plan1".to_owned(),
+            description: "test plan1".to_owned(),
+            asset: AssetInfo::Cw20 { addr: token_contract.clone() },
+            tiers: vec![Tier {
+                tier_id: 0,
+                amount: 1u128.into(),
+            }],
+            billing_mode: BillingMode::Flat,
+            cron: "* * * * *".parse::<CronSpec>().unwrap().compile().unwrap(),
+            tzoffset: 0,
+            goal: None,
+            deadline: None,
+        };
+        let plan_msg = ExecuteMsg::CreatePlan(content.clone());
+        let rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(merchant.as_ref(), &[]),
+            plan_msg,
+        )
+        .unwrap();
+        let plan_id: Uint128 = rsp.attributes[1].value.parse::<u128>().unwrap().into();
+
+        // query one plan
+        let plan: Plan =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Plan { plan_id }).unwrap())
+                .unwrap();
+        assert_eq!(
+            plan,
+            Plan {
+                id: 1u128.into(),
+                owner: Addr::unchecked(merchant),
+                content: content.clone().validate(&deps.api, &env.block).unwrap(),
+                deposit: vec![],
+                collected: Uint128::zero(),
+            }
+        );
+
+        // list plans
+        let plans: PlansResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ListPlans {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(plans.plans.len(), 1);
+        let plans: PlansResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ListPlans {
+                    start_after: Some(plans.plans[0].id),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(plans.plans.len(), 0);
+
+        // subscribe, next_collection_time is now derived from the plan's
+        // cron schedule instead of trusted from the caller
+        let now: i64 = env.block.time.seconds() as i64;
+        let expected_next = content
+            .clone()
+            .validate(&deps.api, &env.block)
+            .unwrap()
+            .next_collection_time(now)
+            .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(user.as_ref(), &[]),
+            ExecuteMsg::Subscribe {
+                plan_id: 1u128.into(),
+                tier_id: 0,
+                expires: Expiration::Never {},
+            },
+        )
+        .unwrap();
+
+        // query collectible subscriptions
+        {
+            let mut env = env.clone();
+            let rsp: SubscriptionsResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::CollectibleSubscriptions { limit: None },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(rsp.subscriptions.len(), 0);
+
+            env.block.time = Timestamp::from_seconds(expected_next as u64);
+            let rsp: SubscriptionsResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::CollectibleSubscriptions { limit: None },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(rsp.subscriptions.len(), 1);
+
+            // collect payment
+            let (plan_id, subscriber, sub) = rsp.subscriptions[0].clone();
+
+            // test validations: a stale current_collection_time is rejected
+            assert_eq!(
+                execute(
+                    deps.as_mut(),
+                    env.clone(),
+                    mock_info(user.as_ref(), &[]),
+                    ExecuteMsg::Collection {
+                        items: vec![CollectOne {
+                            plan_id,
+                            subscriber: subscriber.clone().into(),
+                            current_collection_time: 0,
+                            tier_id: 0,
+                            usage_units: None,
+                        }],
+                    },
+                )
+                .unwrap()
+                .messages
+                .len(),
+                0
+            );
+
+            // success path
+            let rsp = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(user.as_ref(), &[]),
+                ExecuteMsg::Collection {
+                    items: vec![CollectOne {
+                        plan_id,
+                        subscriber: subscriber.into(),
+                        current_collection_time: sub.next_collection_time,
+                        tier_id: 0,
+                        usage_units: None,
+                    }],
+                },
+            )
+            .unwrap();
+            // one cw20 transfer submessage for each successful payment
+            // collection, dispatched with `reply_on_error` so `reply` can
+            // roll the subscription back if the `TransferFrom` fails
+            assert_eq!(rsp.messages.len(), 1);
+
+            // query the subscription directly: next_collection_time was
+            // derived from the cron schedule, not trusted from the caller
+            let stored_sub: Subscription = from_binary(
+                &query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::Subscription {
+                        plan_id,
+                        subscriber: user.clone(),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(
+                stored_sub.next_collection_time,
+                content
+                    .validate(&deps.api, &env.block)
+                    .unwrap()
+                    .next_collection_time(sub.next_collection_time)
+                    .unwrap()
+            );
+
+            // list subscriptions of the plan
+            let subs: SubscriptionsResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::ListSubscriptions {
+                        plan_id,
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(subs.subscriptions.len(), 1);
+        }
+    }
+
+    #[test]
+    fn reply_rolls_back_and_auto_unsubscribes_after_max_failures() {
+        let merchant = String::from("merchant");
+        let user = String::from("user");
+
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            InitMsg {
+                params: Params {
+                    required_deposit_plan: vec![],
+                    required_deposit_subscription: vec![],
+                    max_consecutive_failures: Some(2),
+                    prorate_on_cancel: false,
+                    nft_collection: None,
+                },
+            },
+        )
+        .unwrap();
+
+        let content = PlanContent::<String> {
+            title: "test plan1".to_owned(),
+            description: "test plan1".to_owned(),
+            asset: AssetInfo::Cw20 { addr: "cw20-contract".to_owned() },
+            tiers: vec![Tier {
+                tier_id: 0,
+                amount: 1u128.into(),
+            }],
+            billing_mode: BillingMode::Flat,
+            cron: "* * * * *".parse::<CronSpec>().unwrap().compile().unwrap(),
+            tzoffset: 0,
+            goal: None,
+            deadline: None,
+        };
+        let rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(merchant.as_ref(), &[]),
+            ExecuteMsg::CreatePlan(content),
+        )
+        .unwrap();
+        let plan_id: Uint128 = rsp.attributes[1].value.parse::<u128>().unwrap().into();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(user.as_ref(), &[]),
+            ExecuteMsg::Subscribe {
+                plan_id,
+                tier_id: 0,
+                expires: Expiration::Never {},
+            },
+        )
+        .unwrap();
+
+        let mut env = env;
+        let original: Subscription = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Subscription {
+                    plan_id,
+                    subscriber: user.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        env.block.time = Timestamp::from_seconds(original.next_collection_time as u64);
+
+        // first failed collection: rolled back, not yet unsubscribed
+        let collect_rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(user.as_ref(), &[]),
+            ExecuteMsg::Collection {
+                items: vec![CollectOne {
+                    plan_id,
+                    subscriber: user.clone(),
+                    current_collection_time: original.next_collection_time,
+                    tier_id: 0,
+                    usage_units: None,
+                }],
+            },
+        )
+        .unwrap();
+        let reply_id = collect_rsp.messages[0].id;
+        let reply_rsp = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: reply_id,
+                result: cosmwasm_std::ContractResult::Err("transfer failed".to_owned()),
+            },
+        )
+        .unwrap();
+        assert_eq!(reply_rsp.messages.len(), 0);
+        assert_eq!(reply_rsp.attributes[4].value, "false");
+
+        let after_first_failure: Subscription = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Subscription {
+                    plan_id,
+                    subscriber: user.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(after_first_failure.consecutive_failures, 1);
+        assert_eq!(
+            after_first_failure.next_collection_time,
+            original.next_collection_time
+        );
+
+        // second failed collection at the same period: exceeds
+        // max_consecutive_failures, auto-unsubscribes and refunds
+        let collect_rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(user.as_ref(), &[]),
+            ExecuteMsg::Collection {
+                items: vec![CollectOne {
+                    plan_id,
+                    subscriber: user.clone(),
+                    current_collection_time: original.next_collection_time,
+                    tier_id: 0,
+                    usage_units: None,
+                }],
+            },
+        )
+        .unwrap();
+        let reply_id = collect_rsp.messages[0].id;
+        let reply_rsp = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: reply_id,
+                result: cosmwasm_std::ContractResult::Err("transfer failed".to_owned()),
+            },
+        )
+        .unwrap();
+        // this plan's asset is Cw20 and nothing was deposited on `Subscribe`,
+        // so there's nothing left to refund once the subscription is closed
+        assert_eq!(reply_rsp.messages.len(), 0);
+        assert_eq!(reply_rsp.attributes[4].value, "true");
+
+        let err = query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Subscription {
+                plan_id,
+                subscriber: user,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, cosmwasm_std::StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn crowdfunding_plan_releases_once_goal_met_or_refunds_if_not() {
+        let merchant = String::from("merchant");
+        let backer1 = String::from("backer1");
+        let backer2 = String::from("backer2");
+
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            InitMsg {
+                params: Params {
+                    required_deposit_plan: vec![],
+                    required_deposit_subscription: vec![],
+                    max_consecutive_failures: None,
+                    prorate_on_cancel: false,
+                    nft_collection: None,
+                },
+            },
+        )
+        .unwrap();
+
+        let now: i64 = env.block.time.seconds() as i64;
+        let deadline = now + 120;
+        let content = PlanContent::<String> {
+            title: "crowdfunding plan".to_owned(),
+            description: "crowdfunding plan".to_owned(),
+            asset: AssetInfo::Cw20 { addr: "cw20-contract".to_owned() },
+            tiers: vec![Tier {
+                tier_id: 0,
+                amount: 1u128.into(),
+            }],
+            billing_mode: BillingMode::Flat,
+            cron: "* * * * *".parse::<CronSpec>().unwrap().compile().unwrap(),
+            tzoffset: 0,
+            goal: Some(2u128.into()),
+            deadline: Some(Expiration::AtTime(Timestamp::from_seconds(deadline as u64))),
+        };
+        let rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(merchant.as_ref(), &[]),
+            ExecuteMsg::CreatePlan(content),
+        )
+        .unwrap();
+        let plan_id: Uint128 = rsp.attributes[1].value.parse::<u128>().unwrap().into();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(backer1.as_ref(), &[]),
+            ExecuteMsg::Subscribe {
+                plan_id,
+                tier_id: 0,
+                expires: Expiration::Never {},
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(backer2.as_ref(), &[]),
+            ExecuteMsg::Subscribe {
+                plan_id,
+                tier_id: 0,
+                expires: Expiration::Never {},
+            },
+        )
+        .unwrap();
+
+        let mut env = env;
+        let backer1_sub: Subscription = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Subscription {
+                    plan_id,
+                    subscriber: backer1.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        env.block.time = Timestamp::from_seconds(backer1_sub.next_collection_time as u64);
+
+        // collect one period from each backer; both transfers land in the
+        // contract's pool since the plan has a goal, confirmed via `reply`
+        collect_and_confirm(&mut deps, &env, plan_id, &backer1, backer1_sub.next_collection_time);
+        collect_and_confirm(&mut deps, &env, plan_id, &backer2, backer1_sub.next_collection_time);
+
+        let plan: Plan =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Plan { plan_id }).unwrap())
+                .unwrap();
+        assert_eq!(plan.collected, Uint128::from(2u128));
+
+        // releasing before the deadline passes is rejected even though the
+        // goal was already reached
+        assert!(matches!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(merchant.as_ref(), &[]),
+                ExecuteMsg::ReleasePlan { plan_id },
+            )
+            .unwrap_err(),
+            ContractError::CampaignNotClosed
+        ));
+
+        env.block.time = Timestamp::from_seconds(deadline as u64 + 1);
+
+        // once closed, the owner can sweep the whole pool in one transfer
+        let release_rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(merchant.as_ref(), &[]),
+            ExecuteMsg::ReleasePlan { plan_id },
+        )
+        .unwrap();
+        assert_eq!(release_rsp.messages.len(), 1);
+
+        let plan: Plan =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Plan { plan_id }).unwrap())
+                .unwrap();
+        assert_eq!(plan.collected, Uint128::zero());
+
+        // a second campaign that never reaches its goal: backers can reclaim
+        // their individual contributions once the deadline passes
+        let content = PlanContent::<String> {
+            title: "unfunded campaign".to_owned(),
+            description: "unfunded campaign".to_owned(),
+            asset: AssetInfo::Cw20 { addr: "cw20-contract".to_owned() },
+            tiers: vec![Tier {
+                tier_id: 0,
+                amount: 1u128.into(),
+            }],
+            billing_mode: BillingMode::Flat,
+            cron: "* * * * *".parse::<CronSpec>().unwrap().compile().unwrap(),
+            tzoffset: 0,
+            goal: Some(10u128.into()),
+            deadline: Some(Expiration::AtTime(env.block.time.plus_seconds(120))),
+        };
+        let rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(merchant.as_ref(), &[]),
+            ExecuteMsg::CreatePlan(content),
+        )
+        .unwrap();
+        let plan_id: Uint128 = rsp.attributes[1].value.parse::<u128>().unwrap().into();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(backer1.as_ref(), &[]),
+            ExecuteMsg::Subscribe {
+                plan_id,
+                tier_id: 0,
+                expires: Expiration::Never {},
+            },
+        )
+        .unwrap();
+        let sub: Subscription = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Subscription {
+                    plan_id,
+                    subscriber: backer1.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        env.block.time = Timestamp::from_seconds(sub.next_collection_time as u64);
+        collect_and_confirm(&mut deps, &env, plan_id, &backer1, sub.next_collection_time);
+
+        // reclaim is rejected before the deadline
+        assert!(matches!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(backer1.as_ref(), &[]),
+                ExecuteMsg::ReclaimContribution { plan_id },
+            )
+            .unwrap_err(),
+            ContractError::CampaignNotClosed
+        ));
+
+        env.block.time = env.block.time.plus_seconds(121);
+        let reclaim_rsp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(backer1.as_ref(), &[]),
+            ExecuteMsg::ReclaimContribution { plan_id },
+        )
+        .unwrap();
+        assert_eq!(reclaim_rsp.messages.len(), 1);
+
+        // a contribution can only be reclaimed once
+        assert!(matches!(
+            execute(
+                deps.as_mut(),
+                env,
+                mock_info(backer1.as_ref(), &[]),
+                ExecuteMsg::ReclaimContribution { plan_id },
+            )
+            .unwrap_err(),
+            ContractError::NothingToReclaim
+        ));
+    }
 }
@@ -1,9 +1,13 @@
-use chrono::{Datelike, NaiveDateTime, Timelike};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::bitset::{BitSetIndex, NonEmptyBitSet};
 
+/// Bound the search for the next occurrence so an impossible spec (e.g. Feb 30)
+/// can't loop forever.
+const MAX_SEARCH_DAYS: i64 = 366 * 4;
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct CronCompiled {
     pub minute: NonEmptyBitSet,
@@ -14,8 +18,10 @@ pub struct CronCompiled {
 }
 
 impl CronCompiled {
-    /// Verify the datetime matches cron spec
-    pub fn verify(&self, datetime: NaiveDateTime) -> bool {
+    /// Verify the UTC unix timestamp matches the cron spec, at minute
+    /// precision.
+    pub fn verify(&self, ts: i64) -> bool {
+        let datetime = DateTime::from_timestamp(ts, 0).unwrap().naive_utc();
         let time = datetime.time();
         let date = datetime.date();
         // SAFETY: range of value is guaranteed
@@ -30,11 +36,120 @@ impl CronCompiled {
         let wday = BitSetIndex::new(date.weekday().num_days_from_sunday() as usize).unwrap();
         self.minute.test(minute)
             && self.hour.test(hour)
-            && self.mday.test(mday)
+            && self.day_matches(mday, wday)
             && self.month.test(month)
-            && self.wday.test(wday)
             && time.second() == 0
-            && time.nanosecond() == 0
+    }
+
+    /// Classic cron day matching: when both `mday` and `wday` are restricted
+    /// (neither is the full range), a day matches if *either* matches;
+    /// otherwise both must match.
+    fn day_matches(&self, mday: BitSetIndex, wday: BitSetIndex) -> bool {
+        let mday_restricted = Self::is_restricted(self.mday, 1, 31);
+        let wday_restricted = Self::is_restricted(self.wday, 0, 6);
+        if mday_restricted && wday_restricted {
+            self.mday.test(mday) || self.wday.test(wday)
+        } else {
+            self.mday.test(mday) && self.wday.test(wday)
+        }
+    }
+
+    /// Is `field` restricted to a proper subset of `[min, max]`, i.e. not `*`?
+    fn is_restricted(field: NonEmptyBitSet, min: usize, max: usize) -> bool {
+        field != NonEmptyBitSet::from_range(min, max)
+    }
+
+    /// Compute the smallest UTC unix timestamp strictly greater than `from`
+    /// that matches this compiled cron spec, so the contract can derive
+    /// `next_collection_time` itself instead of trusting a client-supplied
+    /// value. Returns `None` if no match is found within `MAX_SEARCH_DAYS`.
+    pub fn next_after(&self, from: i64) -> Option<i64> {
+        let min_hour = self.hour.min().get() as u32;
+        let min_minute = self.minute.min().get() as u32;
+
+        let after = DateTime::from_timestamp(from, 0).unwrap().naive_utc();
+        let mut date = after.date();
+        let mut hour = after.time().hour();
+        let mut minute = after.time().minute() + 1;
+        if minute == 60 {
+            minute = 0;
+            hour += 1;
+        }
+        if hour == 24 {
+            hour = 0;
+            date = date.succ_opt().unwrap();
+        }
+
+        for _ in 0..MAX_SEARCH_DAYS {
+            // (1) month: jump straight to the next allowed month, rolling
+            // over to next year if this year has none left.
+            let month_idx = BitSetIndex::new(date.month() as usize)?;
+            match self.month.next_set(month_idx) {
+                Some(next) if next == month_idx => {}
+                Some(next) => {
+                    date = NaiveDate::from_ymd_opt(date.year(), next.get() as u32, 1).unwrap();
+                    hour = min_hour;
+                    minute = min_minute;
+                    continue;
+                }
+                None => {
+                    date = NaiveDate::from_ymd_opt(date.year() + 1, self.month.min().get() as u32, 1).unwrap();
+                    hour = min_hour;
+                    minute = min_minute;
+                    continue;
+                }
+            }
+
+            // (2) day
+            let mday_idx = BitSetIndex::new(date.day() as usize)?;
+            let wday_idx = BitSetIndex::new(date.weekday().num_days_from_sunday() as usize)?;
+            if !self.day_matches(mday_idx, wday_idx) {
+                date = date.succ_opt().unwrap();
+                hour = min_hour;
+                minute = min_minute;
+                continue;
+            }
+
+            // (3) hour
+            let hour_idx = BitSetIndex::new(hour as usize)?;
+            match self.hour.next_set(hour_idx) {
+                Some(next) if next == hour_idx => {}
+                Some(next) => {
+                    hour = next.get() as u32;
+                    minute = min_minute;
+                    continue;
+                }
+                None => {
+                    date = date.succ_opt().unwrap();
+                    hour = min_hour;
+                    minute = min_minute;
+                    continue;
+                }
+            }
+
+            // (4) minute
+            let minute_idx = BitSetIndex::new(minute as usize)?;
+            match self.minute.next_set(minute_idx) {
+                Some(next) if next == minute_idx => {}
+                Some(next) => minute = next.get() as u32,
+                None => {
+                    hour += 1;
+                    minute = min_minute;
+                    if hour == 24 {
+                        hour = 0;
+                        date = date.succ_opt().unwrap();
+                    }
+                    continue;
+                }
+            }
+
+            return Some(
+                NaiveDateTime::new(date, NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
+                    .and_utc()
+                    .timestamp(),
+            );
+        }
+        None
     }
 }
 
@@ -46,29 +161,67 @@ mod tests {
 
     use crate::cron_spec::CronSpec;
 
+    fn ts(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp()
+    }
+
     #[test]
-    fn cron_verificaton() {
+    fn cron_verification() {
         let cron = CronSpec::from_str("* * * * *").unwrap().compile().unwrap();
-        assert!(!cron.verify(NaiveDate::from_ymd(2016, 7, 8).and_hms(9, 10, 11)));
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 7, 8).and_hms(9, 10, 0)));
+        assert!(!cron.verify(ts(2016, 7, 8, 9, 10) + 11));
+        assert!(cron.verify(ts(2016, 7, 8, 9, 10)));
 
         let cron = CronSpec::from_str("0 0 29 2 *").unwrap().compile().unwrap();
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 0, 0)));
+        assert!(cron.verify(ts(2016, 2, 29, 0, 0)));
 
         let cron = CronSpec::from_str("0 0 29 2 1").unwrap().compile().unwrap();
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 0, 0)));
-        assert!(!cron.verify(NaiveDate::from_ymd(2020, 2, 29).and_hms(0, 0, 0)));
+        // 2016-02-29 is a Monday: matches via the restricted wday side too
+        assert!(cron.verify(ts(2016, 2, 29, 0, 0)));
+        // 2020-02-29 is a Saturday, but matches via the restricted mday side
+        assert!(cron.verify(ts(2020, 2, 29, 0, 0)));
+    }
 
-        let cron = CronSpec::from_str("*/2,*/3 0-10/3 * * *")
-            .unwrap()
-            .compile()
-            .unwrap();
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 0, 0)));
-        assert!(!cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 1, 0)));
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 2, 0)));
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 3, 0)));
-        assert!(cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 4, 0)));
-        assert!(!cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(0, 5, 0)));
-        assert!(!cron.verify(NaiveDate::from_ymd(2016, 2, 29).and_hms(1, 0, 0)));
+    #[test]
+    fn cron_next_after() {
+        let cron = CronSpec::from_str("* * * * *").unwrap().compile().unwrap();
+        assert_eq!(
+            cron.next_after(ts(2016, 7, 8, 9, 10) + 11),
+            Some(ts(2016, 7, 8, 9, 11))
+        );
+
+        let cron = CronSpec::from_str("30 4 * * *").unwrap().compile().unwrap();
+        assert_eq!(
+            cron.next_after(ts(2016, 7, 8, 9, 10)),
+            Some(ts(2016, 7, 9, 4, 30))
+        );
+        assert_eq!(
+            cron.next_after(ts(2016, 7, 9, 4, 29)),
+            Some(ts(2016, 7, 9, 4, 30))
+        );
+
+        // last day of February, leap year only
+        let cron = CronSpec::from_str("0 0 29 2 *").unwrap().compile().unwrap();
+        assert_eq!(
+            cron.next_after(ts(2016, 2, 29, 0, 0)),
+            Some(ts(2020, 2, 29, 0, 0))
+        );
+
+        // impossible spec never matches, bounded search returns None
+        let cron = CronSpec::from_str("0 0 30 2 *").unwrap().compile().unwrap();
+        assert_eq!(cron.next_after(ts(2016, 2, 29, 0, 0)), None);
+
+        // both mday and wday restricted: either matching is enough
+        // wday `1` means Monday; mday `1` means the 1st of the month
+        let cron = CronSpec::from_str("0 0 1 * 1").unwrap().compile().unwrap();
+        // 2021-01-01 is a Friday, but matches via the restricted mday side
+        assert_eq!(
+            cron.next_after(ts(2020, 12, 31, 0, 0)),
+            Some(ts(2021, 1, 1, 0, 0))
+        );
     }
 }
@@ -1,7 +1,7 @@
 //! Parse and compile crontab syntax, not needed for on-chain code.
 use std::str::FromStr;
 
-use crate::bitset::{BitSet, BitSetIndex};
+use crate::bitset::{BitSetIndex, NonEmptyBitSet};
 use crate::cron::CronCompiled;
 
 #[derive(Clone, PartialEq, Debug)]
@@ -17,18 +17,13 @@ pub enum CronItem {
 }
 
 impl CronItem {
-    pub fn compile(&self) -> BitSet {
-        let mut set = BitSet::new();
+    pub fn compile(&self) -> Option<NonEmptyBitSet> {
         match self {
-            Self::Value(value) => set.set(*value),
+            Self::Value(value) => Some(NonEmptyBitSet::new(*value)),
             Self::Range { start, end, step } => {
-                for idx in (start.value()..=end.value()).step_by(step.value()) {
-                    // SAFETY: idx < end.0 < 64
-                    set.set(BitSetIndex::unsafe_new(idx as u8))
-                }
+                NonEmptyBitSet::from_items((start.get()..=end.get()).step_by(step.get()))
             }
-        };
-        set
+        }
     }
 }
 
@@ -38,7 +33,6 @@ pub enum CronError {
     OutOfRange,
 }
 
-/// Empty Vec means `*`
 #[derive(Clone, PartialEq, Debug)]
 pub struct CronSpec {
     pub minute: Vec<CronItem>,
@@ -50,55 +44,35 @@ pub struct CronSpec {
 
 impl CronSpec {
     pub fn compile(&self) -> Result<CronCompiled, CronError> {
-        let minute = compile_component(&self.minute);
-        if let Some(max) = minute.max() {
-            if max.value() > 59 {
-                return Err(CronError::OutOfRange);
-            }
-        } else {
-            return Err(CronError::Empty);
+        let minute = compile_component(&self.minute).ok_or(CronError::Empty)?;
+        if minute.max().get() > 59 {
+            return Err(CronError::OutOfRange);
         }
 
-        let hour = compile_component(&self.hour);
-        if let Some(max) = hour.max() {
-            if max.value() > 23 {
-                return Err(CronError::OutOfRange);
-            }
-        } else {
-            return Err(CronError::Empty);
+        let hour = compile_component(&self.hour).ok_or(CronError::Empty)?;
+        if hour.max().get() > 23 {
+            return Err(CronError::OutOfRange);
         }
 
-        let mday = compile_component(&self.mday);
-        if let Some((min, max)) = mday.bound() {
-            if max.value() > 31 {
-                return Err(CronError::OutOfRange);
-            }
-            if min.value() < 1 {
-                return Err(CronError::OutOfRange);
-            }
-        } else {
-            return Err(CronError::Empty);
+        let mday = compile_component(&self.mday).ok_or(CronError::Empty)?;
+        if mday.max().get() > 31 {
+            return Err(CronError::OutOfRange);
+        }
+        if mday.min().get() < 1 {
+            return Err(CronError::OutOfRange);
         }
 
-        let month = compile_component(&self.month);
-        if let Some((min, max)) = month.bound() {
-            if max.value() > 12 {
-                return Err(CronError::OutOfRange);
-            }
-            if min.value() < 1 {
-                return Err(CronError::OutOfRange);
-            }
-        } else {
-            return Err(CronError::Empty);
+        let month = compile_component(&self.month).ok_or(CronError::Empty)?;
+        if month.max().get() > 12 {
+            return Err(CronError::OutOfRange);
+        }
+        if month.min().get() < 1 {
+            return Err(CronError::OutOfRange);
         }
 
-        let wday = compile_component(&self.wday);
-        if let Some(max) = wday.max() {
-            if max.value() > 6 {
-                return Err(CronError::OutOfRange);
-            }
-        } else {
-            return Err(CronError::Empty);
+        let wday = compile_component(&self.wday).ok_or(CronError::Empty)?;
+        if wday.max().get() > 6 {
+            return Err(CronError::OutOfRange);
         }
 
         Ok(CronCompiled {
@@ -111,22 +85,55 @@ impl CronSpec {
     }
 }
 
-fn compile_component(items: &[CronItem]) -> BitSet {
-    if items.is_empty() {
-        BitSet::new()
-    } else {
-        let mut set = BitSet::new();
-        for item in items.iter() {
-            set.inplace_union(item.compile());
-        }
-        set
-    }
+fn compile_component(items: &[CronItem]) -> Option<NonEmptyBitSet> {
+    NonEmptyBitSet::from_bitsets(items.iter().filter_map(|item| item.compile()))
 }
 
+/// Three-letter month names, case-insensitive, mapped to the 1-12 range
+/// used by the `month` field.
+const MONTH_NAMES: &[(&str, usize)] = &[
+    ("jan", 1),
+    ("feb", 2),
+    ("mar", 3),
+    ("apr", 4),
+    ("may", 5),
+    ("jun", 6),
+    ("jul", 7),
+    ("aug", 8),
+    ("sep", 9),
+    ("oct", 10),
+    ("nov", 11),
+    ("dec", 12),
+];
+
+/// Three-letter weekday names, case-insensitive, mapped to the 0-6 range
+/// used by the `wday` field (0 is Sunday, matching crontab convention).
+const WDAY_NAMES: &[(&str, usize)] = &[
+    ("sun", 0),
+    ("mon", 1),
+    ("tue", 2),
+    ("wed", 3),
+    ("thu", 4),
+    ("fri", 5),
+    ("sat", 6),
+];
+
 impl FromStr for CronSpec {
     type Err = &'static str;
 
     fn from_str(v: &str) -> Result<Self, Self::Err> {
+        if let Some(nickname) = v.strip_prefix('@') {
+            let expanded = match nickname.to_ascii_lowercase().as_str() {
+                "yearly" | "annually" => "0 0 1 1 *",
+                "monthly" => "0 0 1 * *",
+                "weekly" => "0 0 * * 0",
+                "daily" | "midnight" => "0 0 * * *",
+                "hourly" => "0 * * * *",
+                _ => return Err("unknown cron nickname"),
+            };
+            return expanded.parse();
+        }
+
         let parts: Vec<&str> = v.split(' ').collect();
         if parts.len() != 5 {
             return Err("wrong number of cron components");
@@ -135,28 +142,33 @@ impl FromStr for CronSpec {
         Ok(CronSpec {
             minute: parse_component(
                 parts[0],
-                BitSetIndex::unsafe_new(0),
-                BitSetIndex::unsafe_new(59),
+                BitSetIndex::new(0).unwrap(),
+                BitSetIndex::new(59).unwrap(),
+                None,
             )?,
             hour: parse_component(
                 parts[1],
-                BitSetIndex::unsafe_new(0),
-                BitSetIndex::unsafe_new(23),
+                BitSetIndex::new(0).unwrap(),
+                BitSetIndex::new(23).unwrap(),
+                None,
             )?,
             mday: parse_component(
                 parts[2],
-                BitSetIndex::unsafe_new(1),
-                BitSetIndex::unsafe_new(31),
+                BitSetIndex::new(1).unwrap(),
+                BitSetIndex::new(31).unwrap(),
+                None,
             )?,
             month: parse_component(
                 parts[3],
-                BitSetIndex::unsafe_new(1),
-                BitSetIndex::unsafe_new(12),
+                BitSetIndex::new(1).unwrap(),
+                BitSetIndex::new(12).unwrap(),
+                Some(MONTH_NAMES),
             )?,
             wday: parse_component(
                 parts[4],
-                BitSetIndex::unsafe_new(0),
-                BitSetIndex::unsafe_new(6),
+                BitSetIndex::new(0).unwrap(),
+                BitSetIndex::new(6).unwrap(),
+                Some(WDAY_NAMES),
             )?,
         })
     }
@@ -166,12 +178,13 @@ fn parse_component(
     v: &str,
     min: BitSetIndex,
     max: BitSetIndex,
+    names: Option<&[(&str, usize)]>,
 ) -> Result<Vec<CronItem>, &'static str> {
     let mut result = Vec::new();
     for item in v.split(',') {
         let parts: Vec<&str> = item.split('/').collect();
         if parts.len() == 1 || parts.len() == 2 {
-            let step = parse_number(parts.get(1).unwrap_or(&"1"))?;
+            let step = parse_number(parts.get(1).unwrap_or(&"1"), names)?;
             let parts: Vec<&str> = parts[0].split('-').collect();
             if parts.len() == 1 {
                 if parts[0] == "*" {
@@ -183,13 +196,13 @@ fn parse_component(
                     });
                 } else {
                     // value
-                    result.push(CronItem::Value(parse_number(parts[0])?));
+                    result.push(CronItem::Value(parse_number(parts[0], names)?));
                 }
             } else if parts.len() == 2 {
                 // range
                 result.push(CronItem::Range {
-                    start: parse_number(parts[0])?,
-                    end: parse_number(parts[1])?,
+                    start: parse_number(parts[0], names)?,
+                    end: parse_number(parts[1], names)?,
                     step,
                 });
             } else {
@@ -202,7 +215,12 @@ fn parse_component(
     Ok(result)
 }
 
-fn parse_number(v: &str) -> Result<BitSetIndex, &'static str> {
+fn parse_number(v: &str, names: Option<&[(&str, usize)]>) -> Result<BitSetIndex, &'static str> {
+    if let Some(names) = names {
+        if let Some((_, n)) = names.iter().find(|(name, _)| v.eq_ignore_ascii_case(name)) {
+            return BitSetIndex::new(*n).ok_or("cron number out of range");
+        }
+    }
     let n = v.parse::<usize>().map_err(|_| "invalid cron number")?;
     BitSetIndex::new(n).ok_or("cron number out of range")
 }
@@ -214,11 +232,11 @@ mod tests {
     #[test]
     fn cron_compile() {
         const FULL_CRON: CronCompiled = CronCompiled {
-            minute: BitSet::from_range(0, 59),
-            hour: BitSet::from_range(0, 23),
-            mday: BitSet::from_range(1, 31),
-            wday: BitSet::from_range(0, 6),
-            month: BitSet::from_range(1, 12),
+            minute: NonEmptyBitSet::from_range(0, 59),
+            hour: NonEmptyBitSet::from_range(0, 23),
+            mday: NonEmptyBitSet::from_range(1, 31),
+            wday: NonEmptyBitSet::from_range(0, 6),
+            month: NonEmptyBitSet::from_range(1, 12),
         };
 
         let full = "* * * * *".parse::<CronSpec>().unwrap();
@@ -230,4 +248,28 @@ mod tests {
         let steps = "*/2,*/3 1-10/3 * * *".parse::<CronSpec>().unwrap();
         steps.compile().unwrap();
     }
+
+    #[test]
+    fn cron_names_and_nicknames() {
+        let named = "30 4 * JAN-mar MON".parse::<CronSpec>().unwrap();
+        let numeric = "30 4 * 1-3 1".parse::<CronSpec>().unwrap();
+        assert_eq!(named, numeric);
+
+        for (nickname, equivalent) in [
+            ("@yearly", "0 0 1 1 *"),
+            ("@annually", "0 0 1 1 *"),
+            ("@monthly", "0 0 1 * *"),
+            ("@weekly", "0 0 * * 0"),
+            ("@daily", "0 0 * * *"),
+            ("@midnight", "0 0 * * *"),
+            ("@hourly", "0 * * * *"),
+        ] {
+            assert_eq!(
+                nickname.parse::<CronSpec>().unwrap(),
+                equivalent.parse::<CronSpec>().unwrap()
+            );
+        }
+
+        assert!("@fortnightly".parse::<CronSpec>().is_err());
+    }
 }
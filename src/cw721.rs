@@ -0,0 +1,44 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cw0::Expiration;
+
+/// Subset of an external cw721 (cw721-expiration flavor) collection
+/// contract's `ExecuteMsg` this contract drives when `Params.nft_collection`
+/// is set. The rest of that contract's interface (queries, transfer
+/// approvals, ...) belongs to that contract, not here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint(Cw721MintMsg),
+    TransferNft { recipient: String, token_id: String },
+    /// cw721-base's generic escape hatch for collection-specific behavior;
+    /// the target collection is expected to support `UpdateExpiration` so a
+    /// subscription's `UpdateExpires` can keep the NFT's expiration in sync.
+    Extension { msg: Cw721ExtensionMsg },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Cw721MintMsg {
+    pub token_id: String,
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: Cw721Extension,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Cw721Extension {
+    /// Once this passes, the collection's `OwnerOf`/`NftInfo` queries report
+    /// the token as expired rather than returning a live owner, mirroring
+    /// `Subscription.expires`.
+    pub expiration: Expiration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExtensionMsg {
+    UpdateExpiration {
+        token_id: String,
+        expiration: Expiration,
+    },
+}
@@ -0,0 +1,62 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+    #[error("not enough deposit")]
+    NotEnoughDeposit,
+    #[error("wrong deposit coin")]
+    WrongDepositCoin,
+    #[error("plan not exists")]
+    PlanNotExists,
+    #[error("invalid expires")]
+    InvalidExpires,
+    #[error("subscription expired")]
+    SubscriptionExpired,
+    #[error("subscription exists")]
+    SubscriptionExists,
+    #[error("invalid timezone offset")]
+    InvalidTimeZoneOffset,
+    #[error("the sender is not plan owner")]
+    NotPlanOwner,
+    #[error("invalid input coins")]
+    InvalidCoins,
+    #[error("invalid collection time")]
+    InvalidCollectionTime,
+    #[error("unknown reply id")]
+    UnknownReplyId,
+    #[error("invalid migration")]
+    InvalidMigration,
+    #[error("invalid funding goal")]
+    InvalidGoal,
+    #[error("plan has no funding goal")]
+    NotCrowdfundingPlan,
+    #[error("campaign deadline has not passed yet")]
+    CampaignNotClosed,
+    #[error("funding goal was not reached")]
+    GoalNotMet,
+    #[error("funding goal was already reached")]
+    GoalAlreadyMet,
+    #[error("nothing to reclaim")]
+    NothingToReclaim,
+    #[error("invalid denom")]
+    InvalidDenom,
+    #[error("escrow is only supported for native-asset plans")]
+    EscrowNotSupported,
+    #[error("plan tiers must be non-empty, with unique non-zero-amount ids")]
+    InvalidTiers,
+    #[error("plan has no tier with this id")]
+    UnknownTier,
+    #[error("metered billing requires a non-zero unit price")]
+    InvalidBillingMode,
+    #[error("a metered plan's collection must report usage_units")]
+    MissingUsageUnits,
+    #[error("arithmetic overflow computing a billed amount")]
+    ProrationOverflow,
+    #[error("plan title is too long")]
+    TitleTooLong,
+    #[error("plan description is too long")]
+    DescriptionTooLong,
+}
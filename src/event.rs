@@ -1,6 +1,20 @@
-use cosmwasm_std::{Response, Uint128};
+use cosmwasm_std::{Coin, Response, Uint128};
 use cw0::Event;
 
+/// Render a coin list the same way `Coin`'s own `Display` does, joined by
+/// commas. Event attribute values can't be empty, so an empty list renders
+/// as `"none"` rather than `""`.
+fn coins_to_string(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return "none".to_owned();
+    }
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 pub struct SubscribeEvent<'a> {
     pub plan_id: Uint128,
     pub subscriber: &'a str,
@@ -8,22 +22,31 @@ pub struct SubscribeEvent<'a> {
 
 impl<'a> Event for SubscribeEvent<'a> {
     fn add_attributes(&self, rsp: &mut Response) {
-        rsp.add_attribute("action", "subscribe");
-        rsp.add_attribute("plan_id", self.plan_id);
-        rsp.add_attribute("subscriber", self.subscriber);
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "subscribe")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber);
     }
 }
 
 pub struct UnsubscribeEvent<'a> {
     pub plan_id: Uint128,
     pub subscriber: &'a str,
+    /// Share of the deposit credited to the plan owner for service already
+    /// rendered this period, empty unless `Params.prorate_on_cancel` applied
+    pub earned: &'a [Coin],
+    /// Share of the deposit refunded to the subscriber
+    pub refunded: &'a [Coin],
 }
 
 impl<'a> Event for UnsubscribeEvent<'a> {
     fn add_attributes(&self, rsp: &mut Response) {
-        rsp.add_attribute("action", "unsubscribe");
-        rsp.add_attribute("plan_id", self.plan_id);
-        rsp.add_attribute("subscriber", self.subscriber);
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "unsubscribe")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute("earned", coins_to_string(self.earned))
+            .add_attribute("refunded", coins_to_string(self.refunded));
     }
 }
 
@@ -33,8 +56,23 @@ pub struct CreatePlanEvent {
 
 impl Event for CreatePlanEvent {
     fn add_attributes(&self, rsp: &mut Response) {
-        rsp.add_attribute("action", "create-plan");
-        rsp.add_attribute("plan_id", self.plan_id);
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "create-plan")
+            .add_attribute("plan_id", self.plan_id);
+    }
+}
+
+pub struct UpdateSubscriptionEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+}
+
+impl<'a> Event for UpdateSubscriptionEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "update-expires")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber);
     }
 }
 
@@ -44,7 +82,145 @@ pub struct StopPlanEvent {
 
 impl Event for StopPlanEvent {
     fn add_attributes(&self, rsp: &mut Response) {
-        rsp.add_attribute("action", "stop-plan");
-        rsp.add_attribute("plan_id", self.plan_id);
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "stop-plan")
+            .add_attribute("plan_id", self.plan_id);
+    }
+}
+
+pub struct ReleasePlanEvent {
+    pub plan_id: Uint128,
+    pub amount: Uint128,
+}
+
+impl Event for ReleasePlanEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "release-plan")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("amount", self.amount);
+    }
+}
+
+pub struct ReclaimContributionEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for ReclaimContributionEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "reclaim-contribution")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute("amount", self.amount);
+    }
+}
+
+pub struct TransferSubscriptionEvent<'a> {
+    pub plan_id: Uint128,
+    pub from: &'a str,
+    pub to: &'a str,
+}
+
+impl<'a> Event for TransferSubscriptionEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "transfer-subscription")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("from", self.from)
+            .add_attribute("to", self.to);
+    }
+}
+
+pub struct UpdateTierEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+    pub tier_id: u64,
+}
+
+impl<'a> Event for UpdateTierEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "update-tier")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute("tier_id", self.tier_id.to_string());
+    }
+}
+
+pub struct DepositBalanceEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for DepositBalanceEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "deposit-balance")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute("amount", self.amount);
+    }
+}
+
+pub struct WithdrawBalanceEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for WithdrawBalanceEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "withdraw-balance")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute("amount", self.amount);
+    }
+}
+
+/// Emitted from `execute_collection` when one tier leg of a `CollectOne`
+/// doesn't resolve to a price the plan still offers, so off-chain callers
+/// can see which leg was dropped instead of only seeing a smaller total.
+pub struct CollectionLegFailedEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+    pub tier_id: u64,
+}
+
+impl<'a> Event for CollectionLegFailedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "collection-leg-failed")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute("tier_id", self.tier_id.to_string());
+    }
+}
+
+/// Emitted from `reply` when a collection's `TransferFrom` submessage
+/// failed, so off-chain collectors can stop resubmitting doomed items
+/// instead of learning about the failure only through silence.
+pub struct CollectionFailedEvent<'a> {
+    pub plan_id: Uint128,
+    pub subscriber: &'a str,
+    pub consecutive_failures: u32,
+    pub unsubscribed: bool,
+}
+
+impl<'a> Event for CollectionFailedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        *rsp = std::mem::take(rsp)
+            .add_attribute("action", "collection-failed")
+            .add_attribute("plan_id", self.plan_id)
+            .add_attribute("subscriber", self.subscriber)
+            .add_attribute(
+                "consecutive_failures",
+                self.consecutive_failures.to_string(),
+            )
+            .add_attribute("unsubscribed", self.unsubscribed.to_string());
     }
 }
@@ -1,17 +1,18 @@
-#![feature(const_option)]
-pub use crate::msg::{ExecuteMsg, InitMsg};
+pub use crate::msg::{
+    AssetInfo, BillingMode, CollectOne, ExecuteMsg, InitMsg, MigrateMsg, Params, PlanContent, Tier,
+};
 pub use crate::query::{PlansResponse, QueryMsg, SubscriptionsResponse};
 pub use crate::state::{Plan, Subscription};
 
 pub mod bitset;
 pub mod contract;
 pub mod cron;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cron_spec;
 
+mod cw721;
 mod error;
 mod event;
 mod msg;
 mod query;
 mod state;
-
-#[cfg(any(test, feature = "off-chain"))]
-pub mod cron_spec;
@@ -1,19 +1,76 @@
-use chrono::offset::{FixedOffset, TimeZone};
+use std::collections::HashSet;
+
+use chrono::offset::FixedOffset;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Coin, Uint128};
+use cosmwasm_std::{Addr, Api, BlockInfo, Coin, Uint128};
 use cw0::Expiration;
 
 use crate::cron::CronCompiled;
 use crate::error::ContractError;
 
+const MAX_DESCRIPTION_LENGTH: usize = 5000;
+const MAX_TITLE_LENGTH: usize = 140;
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Params {
     /// Minimal native tokens deposit need for each plan, will refunded after deleted
     pub required_deposit_plan: Vec<Coin>,
     /// Minimal native tokens deposit need for each subscription, will refunded after deleted
     pub required_deposit_subscription: Vec<Coin>,
+    /// Auto-unsubscribe after this many consecutive failed collections in a
+    /// row for a subscription. `None` disables auto-unsubscribe.
+    pub max_consecutive_failures: Option<u32>,
+    /// When set, a subscriber-initiated `Unsubscribe`/`UnsubscribeUser` only
+    /// refunds the deposit prorated by how much of the current billing
+    /// interval is left, crediting the plan owner the rest for service
+    /// already rendered; when unset (the default), the whole deposit is
+    /// refunded regardless of elapsed time.
+    pub prorate_on_cancel: bool,
+    /// Address of an existing cw721 (cw721-expiration flavor) collection
+    /// contract. When set, `Subscribe` mints a companion NFT for each new
+    /// subscription straight to the subscriber, who stays its real,
+    /// tradable owner. The subscriber must separately grant this contract
+    /// an `Approve`/`ApproveAll` on the collection so `UpdateExpires` and
+    /// `TransferSubscription` can still call `UpdateExpiration`/
+    /// `TransferNft` on their behalf. `None` disables NFT issuance
+    /// entirely.
+    pub nft_collection: Option<Addr>,
+}
+
+fn has_duplicate_denom(items: &[Coin]) -> bool {
+    let set = items.iter().map(|coin| &coin.denom).collect::<HashSet<_>>();
+    set.len() != items.len()
+}
+
+impl Params {
+    pub fn validate(&self, api: &dyn Api) -> Result<(), ContractError> {
+        if has_duplicate_denom(&self.required_deposit_plan) {
+            return Err(ContractError::InvalidCoins);
+        }
+        if has_duplicate_denom(&self.required_deposit_subscription) {
+            return Err(ContractError::InvalidCoins);
+        }
+        if self
+            .required_deposit_plan
+            .iter()
+            .any(|coin| coin.amount == 0u128.into())
+        {
+            return Err(ContractError::InvalidCoins);
+        }
+        if self
+            .required_deposit_subscription
+            .iter()
+            .any(|coin| coin.amount == 0u128.into())
+        {
+            return Err(ContractError::InvalidCoins);
+        }
+        if let Some(nft_collection) = &self.nft_collection {
+            api.addr_validate(nft_collection.as_str())?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -22,17 +79,18 @@ pub struct InitMsg {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     /// create plan, sender will be the plan owner
-    CreatePlan(PlanContent),
+    CreatePlan(PlanContent<String>),
     /// stop plan, sender must be the plan owner
     StopPlan { plan_id: Uint128 },
-    /// sender subscribe to some plan
+    /// sender subscribe to some plan, at one of its priced tiers
     /// If expiration is set, update if subscription exists
     Subscribe {
         plan_id: Uint128,
+        tier_id: u64,
         expires: Expiration,
-        next_collection_time: i64,
     },
     /// sender unsubscribe to some plan
     Unsubscribe { plan_id: Uint128 },
@@ -41,35 +99,245 @@ pub enum ExecuteMsg {
         plan_id: Uint128,
         subscriber: String,
     },
+    /// Update expires of subscription
+    UpdateExpires {
+        plan_id: Uint128,
+        expires: Expiration,
+    },
+    /// Switch the sender's subscription to a different priced tier of the
+    /// same plan. The remainder of the current period is rescaled by the
+    /// ratio of the old tier's price to the new one, so a switch to a
+    /// cheaper tier stretches the time left and a switch to a pricier one
+    /// shrinks it, instead of charging or refunding money mid-period.
+    UpdateTier { plan_id: Uint128, tier_id: u64 },
     /// Trigger collection of a batch of subscriptions
     Collection { items: Vec<CollectOne> },
+    /// Pay out a crowdfunding plan's pooled `collected` funds to the owner,
+    /// once `goal` has been reached and `deadline` has passed. Sender must
+    /// be the plan owner.
+    ReleasePlan { plan_id: Uint128 },
+    /// Refund the sender's share of a crowdfunding plan's pooled funds,
+    /// once `deadline` has passed without `goal` being reached.
+    ReclaimContribution { plan_id: Uint128 },
+    /// Transfer the sender's subscription, and its companion NFT if one was
+    /// minted, to `recipient`. Reassigns who `Collection` bills and who
+    /// receives service; the recipient must not already be subscribed to
+    /// this plan. Requires the sender to have granted this contract an
+    /// `Approve`/`ApproveAll` on the NFT collection, since the NFT is owned
+    /// by the sender, not this contract.
+    TransferSubscription { plan_id: Uint128, recipient: String },
+    /// Top up the sender's native-asset escrow for `plan_id` with the native
+    /// coins attached to this call, so `Collection` can pull `amount` from it
+    /// instead of needing an allowance (which native tokens don't support).
+    /// Only valid for a plan whose `asset` is `AssetInfo::Native`.
+    DepositBalance { plan_id: Uint128, amount: Uint128 },
+    /// Send `amount` of the sender's native-asset escrow for `plan_id` back
+    /// to the sender. Only valid for a plan whose `asset` is
+    /// `AssetInfo::Native`.
+    WithdrawBalance { plan_id: Uint128, amount: Uint128 },
 }
 
+/// No migration-time parameters needed yet; data fixups are derived purely
+/// from whether `cw2` has a contract version stored yet.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct PlanContent {
+pub struct MigrateMsg {}
+
+/// Which asset a plan is billed in. `Cw20` is pulled from the subscriber's
+/// wallet via `Cw20ExecuteMsg::TransferFrom` on each collection, the same as
+/// before; `Native` can't be pulled by allowance, so it's drawn down from a
+/// prepaid escrow the subscriber tops up via `ExecuteMsg::DepositBalance`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo<A> {
+    Native { denom: String },
+    Cw20 { addr: A },
+}
+
+/// One priced tier a plan offers, addressed by `tier_id` the way cw1155
+/// addresses a token in a batch, e.g. a base fee plus an add-on a subscriber
+/// can opt into separately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Tier {
+    pub tier_id: u64,
+    pub amount: Uint128,
+}
+
+fn has_duplicate_tier_id(tiers: &[Tier]) -> bool {
+    let set = tiers.iter().map(|tier| tier.tier_id).collect::<HashSet<_>>();
+    set.len() != tiers.len()
+}
+
+/// How a plan turns a due collection into a billed amount.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingMode {
+    /// Bill the full resolved tier amount every period
+    Flat,
+    /// Scale the resolved tier amount down by the fraction of the period
+    /// actually elapsed, for a subscriber's still-in-progress first period
+    Prorated,
+    /// Ignore tiers and bill `CollectOne.usage_units * unit_price` instead,
+    /// for pay-as-you-go billing
+    Metered { unit_price: Uint128 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PlanContent<A> {
     pub title: String,
     pub description: String,
-    /// cw20 token address
-    pub token: String,
-    /// Amount to be collected for each period
-    pub amount: Uint128,
+    /// Which asset, and from where, each period's payment is collected in
+    pub asset: AssetInfo<A>,
+    /// Priced tiers a subscriber may choose among; a plan with a single
+    /// price still needs exactly one entry here
+    pub tiers: Vec<Tier>,
+    /// How a due collection's tier total (or usage, for `Metered`) turns
+    /// into the amount actually billed
+    pub billing_mode: BillingMode,
     /// Crontab like specification for the plan
     pub cron: CronCompiled,
     /// timezone for the crontab logic
     pub tzoffset: i32,
+    /// Total amount this plan must raise before `ExecuteMsg::ReleasePlan`
+    /// can pay the owner out of the pool. `None` for an ordinary
+    /// (non-crowdfunding) plan, where collections go straight to the owner
+    /// and `deadline` is unused.
+    pub goal: Option<Uint128>,
+    /// Once this passes, a crowdfunding plan's campaign is decided:
+    /// `ReleasePlan` if `goal` was reached, `ReclaimContribution` for each
+    /// subscriber otherwise.
+    pub deadline: Option<Expiration>,
 }
 
-impl PlanContent {
-    pub fn validate(&self) -> Result<(), ContractError> {
+impl PlanContent<String> {
+    pub fn validate(
+        self,
+        api: &dyn Api,
+        block: &BlockInfo,
+    ) -> Result<PlanContent<Addr>, ContractError> {
+        if self.title.len() > MAX_TITLE_LENGTH {
+            return Err(ContractError::TitleTooLong);
+        }
+        if self.description.len() > MAX_DESCRIPTION_LENGTH {
+            return Err(ContractError::DescriptionTooLong);
+        }
+
         FixedOffset::east_opt(self.tzoffset).ok_or(ContractError::InvalidTimeZoneOffset)?;
-        Ok(())
+        if let Some(deadline) = &self.deadline {
+            if deadline.is_expired(block) {
+                return Err(ContractError::InvalidExpires);
+            }
+        }
+        if self.goal == Some(Uint128::zero()) {
+            return Err(ContractError::InvalidGoal);
+        }
+        if self.tiers.is_empty() || has_duplicate_tier_id(&self.tiers) {
+            return Err(ContractError::InvalidTiers);
+        }
+        if self.tiers.iter().any(|tier| tier.amount.is_zero()) {
+            return Err(ContractError::InvalidTiers);
+        }
+        if let BillingMode::Metered { unit_price } = &self.billing_mode {
+            if unit_price.is_zero() {
+                return Err(ContractError::InvalidBillingMode);
+            }
+        }
+        let asset = match self.asset {
+            AssetInfo::Cw20 { addr } => AssetInfo::Cw20 {
+                addr: api.addr_validate(&addr)?,
+            },
+            AssetInfo::Native { denom } => {
+                if denom.is_empty() {
+                    return Err(ContractError::InvalidDenom);
+                }
+                AssetInfo::Native { denom }
+            }
+        };
+        Ok(PlanContent::<Addr> {
+            title: self.title,
+            description: self.description,
+            asset,
+            tiers: self.tiers,
+            billing_mode: self.billing_mode,
+            cron: self.cron,
+            tzoffset: self.tzoffset,
+            goal: self.goal,
+            deadline: self.deadline,
+        })
+    }
+}
+
+impl<A> PlanContent<A> {
+    /// Shift a UTC unix timestamp into the plan's local wall-clock time,
+    /// which is what the cron fields are matched against.
+    fn to_local(&self, ts: i64) -> i64 {
+        ts + self.tzoffset as i64
     }
 
     pub fn verify_timestamp(&self, ts: i64) -> bool {
-        let datetime = FixedOffset::east(self.tzoffset)
-            .timestamp(ts, 0)
-            .naive_utc();
-        self.cron.verify(datetime)
+        self.cron.verify(self.to_local(ts))
+    }
+
+    /// Price of `tier_id`, or `None` if this plan doesn't offer it.
+    pub fn tier_amount(&self, tier_id: u64) -> Option<Uint128> {
+        self.tiers
+            .iter()
+            .find(|tier| tier.tier_id == tier_id)
+            .map(|tier| tier.amount)
+    }
+
+    /// Compute the next UTC unix timestamp strictly after `ts` at which this
+    /// plan's cron schedule fires, so the contract can derive
+    /// `next_collection_time` itself instead of trusting the caller.
+    pub fn next_collection_time(&self, ts: i64) -> Option<i64> {
+        let next_local = self.cron.next_after(self.to_local(ts))?;
+        Some(next_local - self.tzoffset as i64)
+    }
+
+    /// A crowdfunding plan's campaign is decided once its `deadline` passes;
+    /// a plan with no `deadline` never closes.
+    pub fn campaign_closed(&self, block: &BlockInfo) -> bool {
+        match &self.deadline {
+            Some(deadline) => deadline.is_expired(block),
+            None => false,
+        }
+    }
+
+    /// Turn a resolved tier total (and, for `Metered`, reported usage) into
+    /// the amount actually billed for one collection. `subscription_start`
+    /// is the subscriber's `last_collection_time` going into this
+    /// collection, i.e. the start of the period now being billed.
+    pub fn billed_amount(
+        &self,
+        tier_total: Uint128,
+        usage_units: Option<Uint128>,
+        subscription_start: i64,
+        current_collection_time: i64,
+        next_collection_time: i64,
+    ) -> Result<Uint128, ContractError> {
+        match &self.billing_mode {
+            BillingMode::Flat => Ok(tier_total),
+            BillingMode::Prorated => {
+                let period = next_collection_time - current_collection_time;
+                if period <= 0 {
+                    // nothing to scale by, bill the full amount rather
+                    // than divide by zero
+                    return Ok(tier_total);
+                }
+                let elapsed = (current_collection_time - subscription_start).max(0);
+                // multiply before divide, flooring the result
+                Ok(tier_total
+                    .checked_mul(Uint128::from(elapsed as u128))
+                    .map_err(|_| ContractError::ProrationOverflow)?
+                    .checked_div(Uint128::from(period as u128))
+                    .map_err(|_| ContractError::ProrationOverflow)?)
+            }
+            BillingMode::Metered { unit_price } => {
+                let usage_units = usage_units.ok_or(ContractError::MissingUsageUnits)?;
+                usage_units
+                    .checked_mul(*unit_price)
+                    .map_err(|_| ContractError::ProrationOverflow)
+            }
+        }
     }
 }
 
@@ -78,5 +346,14 @@ pub struct CollectOne {
     pub plan_id: Uint128,
     pub subscriber: String,
     pub current_collection_time: i64,
-    pub next_collection_time: i64,
+    /// Must match the subscriber's own `Subscription::tier_id` -- a
+    /// subscriber can only ever hold one priced tier at a time, so
+    /// `Collection` (which is permissionless) rejects this leg rather than
+    /// billing any other tier, to stop a caller from over-billing a
+    /// subscriber by submitting a pricier tier than they actually
+    /// subscribed to.
+    pub tier_id: u64,
+    /// Usage to bill for a `BillingMode::Metered` plan. Required for such a
+    /// plan, ignored otherwise.
+    pub usage_units: Option<Uint128>,
 }
@@ -25,9 +25,13 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
-    // TODO List all subscriptions of user
-    // ListSubscriptionsOfUser {
-    // },
+    /// List subscriptions of a user across all plans, support pagination,
+    /// response type is SubscriptionsResponse
+    ListSubscriptionsOfUser {
+        subscriber: String,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+    },
     /// List collectible subscriptions, response type is SubscriptionsResponse
     CollectibleSubscriptions { limit: Option<u32> },
 }
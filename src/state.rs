@@ -1,53 +1,144 @@
-use cosmwasm_std::{Addr, Coin, StdResult, Storage, Uint128};
-use cw_storage_plus::{Item, Map, U128Key};
+use std::convert::TryInto;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::msg::{Params, PlanContent};
+use cosmwasm_std::{Addr, Coin, Order, StdError, StdResult, Storage, Uint128};
+use cw0::Expiration;
+use cw_storage_plus::{
+    Bound, I64Key, Index, IndexList, IndexedMap, Item, Map, MultiIndex, U128Key, U64Key,
+};
+
+use crate::bitset::NonEmptyBitSet;
+use crate::cron::CronCompiled;
+use crate::msg::{AssetInfo, BillingMode, Params, PlanContent, Tier};
+
+/// (plan-id, subscriber-address)
+pub type SubscriptionKey<'a> = (U128Key, &'a str);
 
-/// Store the self-incremental unique ids for plans and subscriptions
-pub const PLAN_ID: Item<Uint128> = Item::new("planid");
-pub const SUBSCRIPTION_ID: Item<Uint128> = Item::new("subid");
 /// Store contract params
 pub const PARAMS: Item<Params> = Item::new("params");
 
-/// Store the plans indexed by plan-id
-/// plan-id -> Plan
+/// Store the self-incremental unique ids for plans
+pub const PLAN_ID: Item<Uint128> = Item::new("planid");
+/// Store the plans, `plan-id -> Plan`
 pub const PLANS: Map<U128Key, Plan> = Map::new("plans");
-/// Store the subscriptions indexed by subscription-id
-/// subscription-id -> Subscription
-pub const SUBSCRIPTIONS: Map<U128Key, Subscription> = Map::new("subs");
 
-/// Subscriptions indexed by plan-id for enumeration
-/// (plan-id, subscription-id) -> ()
-pub const PLAN_SUBS: Map<(Uint128, Uint128), ()> = Map::new("plan-subs");
 // /// Subscription queue ordered by expiration time
 // /// (expiration-time, subscription-id) -> ()
 // pub const Q_EXPIRATION: Map<(i64, Uint128), ()> = Map::new("subs-expiration");
 /// Subscription queue ordered by next_collection_time
-/// (next-collection-time, subscription-id) -> ()
-pub const Q_COLLECTION: Map<(i64, Uint128), ()> = Map::new("subs-collection");
+/// (next-collection-time, plan-id, subscriber) -> ()
+pub const Q_COLLECTION: Map<(I64Key, SubscriptionKey), ()> = Map::new("q-collection");
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Prepaid native-asset escrow, `(plan-id, subscriber) -> balance`, topped up
+/// by `ExecuteMsg::DepositBalance` and debited by `Collection` for plans
+/// whose `asset` is `AssetInfo::Native` (native tokens can't be pulled via
+/// allowance the way cw20 ones can).
+pub const NATIVE_ESCROW: Map<SubscriptionKey, Uint128> = Map::new("native-escrow");
+
+/// Next id to hand out for a collection `TransferFrom` submessage
+pub const REPLY_ID: Item<u64> = Item::new("reply-id");
+/// reply-id -> context, so `reply` can map a failed `TransferFrom`
+/// submessage back to the subscription it was collecting for, and undo the
+/// optimistic advance `execute_collection` already made. Cleared once the
+/// reply is handled.
+pub const REPLY_SUBSCRIPTION: Map<U64Key, ReplyContext> = Map::new("reply-subscription");
+
+const ZERO: Uint128 = Uint128::zero();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Plan {
-    id: Uint128,
-    owner: Addr,
-    content: PlanContent,
-    deposit: Coin,
+    pub id: Uint128,
+    pub owner: Addr,
+    pub content: PlanContent<Addr>,
+    pub deposit: Vec<Coin>,
+    /// Cumulative amount collected toward `content.goal`, held by the
+    /// contract until `ReleasePlan`/`ReclaimContribution` resolves the
+    /// campaign. Always zero for a plan with no `goal`.
+    pub collected: Uint128,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Subscription {
-    id: Uint128,
-    plan_id: Uint128,
-    subscriber: Addr,
-    expiration_time: i64,
-    last_collection_time: i64,
-    next_collection_time: i64,
-    deposit: Coin,
+    pub expires: Expiration,
+    /// Priced tier this subscriber signed up for, switchable via
+    /// `ExecuteMsg::UpdateTier`
+    pub tier_id: u64,
+    /// Initialized to current block time created
+    pub last_collection_time: i64,
+    pub next_collection_time: i64,
+    pub deposit: Vec<Coin>,
+    /// Number of collections in a row whose `TransferFrom` failed, reset to
+    /// 0 on the next successful collection
+    pub consecutive_failures: u32,
+    /// Cumulative amount this subscriber has paid into the plan's `goal`
+    /// pool, refundable via `ReclaimContribution` if the deadline passes
+    /// with the goal unmet. Always zero for a plan with no `goal`.
+    pub contributed: Uint128,
+    /// Token id of the companion NFT minted in `Params.nft_collection` for
+    /// this subscription, if any, owned by the subscriber's own wallet.
+    /// `None` when NFT issuance is disabled.
+    pub nft_token_id: Option<String>,
+}
+
+/// Context stashed in `REPLY_SUBSCRIPTION` for a pending collection
+/// `TransferFrom` submessage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReplyContext {
+    pub plan_id: Uint128,
+    pub subscriber: String,
+    /// `Subscription.last_collection_time`/`next_collection_time` before
+    /// `execute_collection` optimistically advanced them, so `reply` can
+    /// restore them if the transfer failed.
+    pub prior_last_collection_time: i64,
+    pub prior_next_collection_time: i64,
+    /// Amount this collection resolved to, so `reply` can credit a
+    /// crowdfunding plan's pool without needing to re-resolve
+    /// `CollectOne.tier_id` itself.
+    pub amount: Uint128,
+}
+
+/// Secondary indexes kept alongside the `(plan-id, subscriber) -> Subscription`
+/// map, so a subscriber's subscriptions can be enumerated without scanning
+/// every plan.
+pub struct SubscriptionIndexes<'a> {
+    /// subscriber -> (plan-id, subscriber); the trailing `Vec<u8>` is the raw
+    /// primary key, required by `MultiIndex` so it can recover the full
+    /// `(plan-id, subscriber)` pair a prefix lookup lands on.
+    pub subscriber: MultiIndex<'a, (String, Vec<u8>), Subscription>,
+}
+
+impl<'a> IndexList<Subscription> for SubscriptionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Subscription>> + '_> {
+        let v: Vec<&dyn Index<Subscription>> = vec![&self.subscriber];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Store the subscriptions, `(plan-id, subscriber) -> Subscription`, indexed
+/// a second time on `subscriber` alone.
+pub fn subscriptions<'a>(
+) -> IndexedMap<'a, SubscriptionKey<'a>, Subscription, SubscriptionIndexes<'a>> {
+    let indexes = SubscriptionIndexes {
+        subscriber: MultiIndex::new(
+            |_sub, pk| {
+                // pk is the raw encoded (plan-id, subscriber) primary key;
+                // the subscriber is whatever's left after the length-prefixed
+                // plan-id is skipped.
+                let (_, subscriber) = decode_key_step(&pk).unwrap();
+                let subscriber = String::from_utf8(subscriber.to_owned()).unwrap();
+                (subscriber, pk)
+            },
+            "plan-subs",
+            "plan-subs__subscriber",
+        ),
+    };
+    IndexedMap::new("plan-subs", indexes)
 }
 
 pub fn gen_plan_id(store: &mut dyn Storage) -> StdResult<Uint128> {
-    let mut plan_id = PLAN_ID.may_load(store)?.unwrap_or(0u64.into());
+    let mut plan_id = PLAN_ID.may_load(store)?.unwrap_or(ZERO);
     plan_id = plan_id.wrapping_add(1u64.into());
     // ensure id not used
     while store
@@ -60,16 +151,273 @@ pub fn gen_plan_id(store: &mut dyn Storage) -> StdResult<Uint128> {
     Ok(plan_id)
 }
 
-pub fn gen_subscription_id(store: &mut dyn Storage) -> StdResult<Uint128> {
-    let mut subscription_id = SUBSCRIPTION_ID.may_load(store)?.unwrap_or(0u64.into());
-    subscription_id = subscription_id.wrapping_add(1u64.into());
-    // ensure id not used
-    while store
-        .get(&SUBSCRIPTIONS.key(U128Key::from(subscription_id.u128())))
-        .is_some()
-    {
-        subscription_id = subscription_id.wrapping_add(1u64.into());
+/// Hand out the next reply id for a collection `TransferFrom` submessage,
+/// recording which subscription it belongs to so `reply` can look it up and,
+/// on failure, undo the optimistic advance.
+pub fn gen_reply_id(store: &mut dyn Storage, ctx: ReplyContext) -> StdResult<u64> {
+    let id = REPLY_ID.may_load(store)?.unwrap_or_default() + 1;
+    REPLY_ID.save(store, &id)?;
+    REPLY_SUBSCRIPTION.save(store, U64Key::from(id), &ctx)?;
+    Ok(id)
+}
+
+/// PANIC: if deserialization failed caused by corrupted storage
+pub fn iter_subscriptions_by_plan(
+    store: &dyn Storage,
+    plan_id: Uint128,
+    start_after: Option<Addr>,
+) -> impl Iterator<Item = (Addr, Subscription)> + '_ {
+    let start = start_after.map(|addr| Bound::exclusive(addr.as_ref()));
+    subscriptions()
+        .prefix(plan_id.u128().into())
+        .range(store, start, None, Order::Ascending)
+        .map(|mpair| {
+            let (k, v) = mpair.unwrap();
+            (Addr::unchecked(String::from_utf8(k).unwrap()), v)
+        })
+}
+
+/// Enumerate every subscription belonging to `subscriber`, across all plans,
+/// using the `subscriber` secondary index instead of scanning `PLANS`.
+///
+/// PANIC: if deserialization failed caused by corrupted storage
+pub fn iter_subscriptions_of_user(
+    store: &dyn Storage,
+    subscriber: Addr,
+    start_after: Option<Uint128>,
+) -> impl Iterator<Item = (Uint128, Subscription)> + '_ {
+    let start = start_after.map(|plan_id| Bound::exclusive(U128Key::from(plan_id.u128())));
+    subscriptions()
+        .idx
+        .subscriber
+        .prefix(subscriber.to_string())
+        .range(store, start, None, Order::Ascending)
+        .map(|mpair| {
+            let (k, v) = mpair.unwrap();
+            // k is the full original (plan-id, subscriber) primary key,
+            // i.e. the length-prefixed plan-id followed by the subscriber
+            let (s, _) = decode_key_step(&k).unwrap();
+            let plan_id = u128::from_be_bytes(s.try_into().unwrap());
+            (plan_id.into(), v)
+        })
+}
+
+/// PANIC: if deserialization failed because of corrupted storage
+pub fn iter_collectible_subscriptions(
+    store: &dyn Storage,
+    now: i64,
+) -> impl Iterator<Item = (i64, Uint128, Addr)> + '_ {
+    let minkey = Q_COLLECTION.key((I64Key::from(0), (U128Key::from(0), "")));
+    let maxkey = Q_COLLECTION.key((
+        I64Key::from(now.checked_add(1).unwrap()),
+        (U128Key::from(0), ""),
+    ));
+    store
+        .range(Some(&minkey), Some(&maxkey), Order::Ascending)
+        .map(|(k, _)| {
+            // decode key, TODO more elegant way?
+            // skip the prefix
+            let (_, k) = decode_key_step(&k).unwrap();
+
+            let (s, k) = decode_key_step(k).unwrap();
+            let collection_time = i64::from_be_bytes(s.try_into().unwrap());
+
+            let (s, k) = decode_key_step(k).unwrap();
+            let plan_id = u128::from_be_bytes(s.try_into().unwrap());
+
+            // the last part is not prefixed with length
+            let addr = Addr::unchecked(String::from_utf8(k.to_owned()).unwrap());
+            (collection_time, plan_id.into(), addr)
+        })
+}
+
+/// decode key, depends on the implemention details in cw-storage-plus
+fn decode_key_step(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    if buf.len() < 2 {
+        return None;
+    }
+    let end = u16::from_be_bytes([buf[0], buf[1]]) as usize + 2;
+    if buf.len() < end {
+        return None;
+    }
+    Some((&buf[2..end], &buf[end..]))
+}
+
+/// Wire-compatible with the pre-consolidation `src/`-tree's `BitSet`: both
+/// are `#[serde(transparent)]` over a `u64`, so this only exists to name the
+/// legacy field types below without pulling in that tree's bitset module.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct LegacyBitSet(u64);
+
+/// Shape of `CronCompiled` under the pre-consolidation `src/`-tree contract,
+/// whose bitset fields may be empty; this contract's own `CronCompiled`
+/// requires each field non-empty, so conversion can fail.
+#[derive(Serialize, Deserialize)]
+struct LegacyCronCompiled {
+    minute: LegacyBitSet,
+    hour: LegacyBitSet,
+    mday: LegacyBitSet,
+    month: LegacyBitSet,
+    wday: LegacyBitSet,
+}
+
+impl LegacyCronCompiled {
+    fn into_current(self) -> StdResult<CronCompiled> {
+        Ok(CronCompiled {
+            minute: legacy_bitset_to_nonempty(self.minute)?,
+            hour: legacy_bitset_to_nonempty(self.hour)?,
+            mday: legacy_bitset_to_nonempty(self.mday)?,
+            month: legacy_bitset_to_nonempty(self.month)?,
+            wday: legacy_bitset_to_nonempty(self.wday)?,
+        })
+    }
+}
+
+fn legacy_bitset_to_nonempty(bits: LegacyBitSet) -> StdResult<NonEmptyBitSet> {
+    if bits.0 == 0 {
+        return Err(StdError::generic_err(
+            "legacy cron field has no bits set, can't migrate to NonEmptyBitSet",
+        ));
+    }
+    Ok(NonEmptyBitSet(bits.0))
+}
+
+/// Shape of the pre-consolidation `src/`-tree's `Timezone`. That contract's
+/// successor only models a fixed UTC offset (`PlanContent::tzoffset`), so a
+/// named, DST-observing zone can't be carried over automatically without
+/// silently changing a plan's billing times across a DST transition.
+#[derive(Serialize, Deserialize)]
+enum LegacyTimezone {
+    FixedOffset(i32),
+    Named(String),
+}
+
+fn legacy_timezone_to_offset(timezone: LegacyTimezone) -> StdResult<i32> {
+    match timezone {
+        LegacyTimezone::FixedOffset(offset) => Ok(offset),
+        LegacyTimezone::Named(_) => Err(StdError::generic_err(
+            "can't migrate a plan with a named timezone to a fixed tzoffset",
+        )),
+    }
+}
+
+/// Shape of the pre-consolidation `src/`-tree's `PaymentKind`, reduced to
+/// the fields needed to build this contract's `AssetInfo`/`Tier` --
+/// `allow_allowance_fallback` and `min_periods` have no equivalent here and
+/// are dropped.
+#[derive(Serialize, Deserialize)]
+enum LegacyPaymentKind {
+    Cw20 { token: String, amount: Uint128 },
+    Native { denom: String, amount: Uint128 },
+}
+
+/// Shape of `PlanContent` under the pre-consolidation `src/`-tree contract,
+/// used only to decode plans stored by that contract before the two trees
+/// were merged into this one.
+#[derive(Serialize, Deserialize)]
+struct LegacyPlanContent {
+    title: String,
+    description: String,
+    payment: LegacyPaymentKind,
+    cron: LegacyCronCompiled,
+    timezone: LegacyTimezone,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LegacyPlan {
+    id: Uint128,
+    owner: Addr,
+    content: LegacyPlanContent,
+    deposit: Vec<Coin>,
+}
+
+/// Shape of `Subscription` under the pre-consolidation `src/`-tree
+/// contract, used only to decode subscriptions stored by that contract
+/// before the two trees were merged into this one.
+#[derive(Serialize, Deserialize)]
+struct LegacySubscription {
+    expires: Expiration,
+    /// `None` until the first collection under the old contract succeeded
+    last_collection_time: Option<i64>,
+    next_collection_time: i64,
+    deposit: Vec<Coin>,
+    missed_collections: u64,
+}
+
+/// Re-save every plan stored by the pre-consolidation `src/`-tree contract
+/// through this contract's generalized `Plan`/`PlanContent` shape: its
+/// single implicit payment becomes tier 0, `billing_mode` defaults to
+/// `Flat` (the old contract always billed the full amount), and there's no
+/// `goal`/`deadline` since that contract had no crowdfunding concept.
+pub fn migrate_legacy_plans(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy: Map<U128Key, LegacyPlan> = Map::new("plans");
+    let old_plans: Vec<LegacyPlan> = legacy
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<_>>()?;
+    for old in old_plans {
+        let (asset, amount) = match old.content.payment {
+            LegacyPaymentKind::Cw20 { token, amount } => {
+                (AssetInfo::Cw20 { addr: Addr::unchecked(token) }, amount)
+            }
+            LegacyPaymentKind::Native { denom, amount } => (AssetInfo::Native { denom }, amount),
+        };
+        let plan = Plan {
+            id: old.id,
+            owner: old.owner,
+            content: PlanContent {
+                title: old.content.title,
+                description: old.content.description,
+                asset,
+                tiers: vec![Tier { tier_id: 0, amount }],
+                billing_mode: BillingMode::Flat,
+                cron: old.content.cron.into_current()?,
+                tzoffset: legacy_timezone_to_offset(old.content.timezone)?,
+                goal: None,
+                deadline: None,
+            },
+            deposit: old.deposit,
+            collected: Uint128::zero(),
+        };
+        PLANS.save(storage, plan.id.u128().into(), &plan)?;
+    }
+    Ok(())
+}
+
+/// Re-save every subscription stored by the pre-consolidation `src/`-tree
+/// contract through this contract's current `Subscription` shape and its
+/// `subscriptions()` indexed map, backfilling the fields that contract
+/// never had: `tier_id: 0` (its only implicit tier), `contributed: 0` and
+/// `nft_token_id: None` (no crowdfunding or NFT issuance existed), and
+/// `last_collection_time: now` for a subscription that had never yet been
+/// collected, so migration doesn't retroactively bill anything.
+pub fn migrate_legacy_subscriptions(storage: &mut dyn Storage, now: i64) -> StdResult<()> {
+    let legacy: Map<SubscriptionKey, LegacySubscription> = Map::new("plan-subs");
+    let old_subs: Vec<(Vec<u8>, LegacySubscription)> = legacy
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (raw_key, old) in old_subs {
+        let (plan_id_bytes, subscriber_bytes) = decode_key_step(&raw_key)
+            .ok_or_else(|| StdError::generic_err("malformed legacy subscription key"))?;
+        let plan_id = u128::from_be_bytes(
+            plan_id_bytes
+                .try_into()
+                .map_err(|_| StdError::generic_err("malformed legacy subscription key"))?,
+        );
+        let subscriber = String::from_utf8(subscriber_bytes.to_owned())
+            .map_err(|_| StdError::generic_err("malformed legacy subscription key"))?;
+        let sub = Subscription {
+            expires: old.expires,
+            tier_id: 0,
+            last_collection_time: old.last_collection_time.unwrap_or(now),
+            next_collection_time: old.next_collection_time,
+            deposit: old.deposit,
+            consecutive_failures: old.missed_collections.try_into().unwrap_or(u32::MAX),
+            contributed: Uint128::zero(),
+            nft_token_id: None,
+        };
+        subscriptions().save(storage, (plan_id.into(), subscriber.as_str()), &sub)?;
     }
-    PLAN_ID.save(store, &subscription_id)?;
-    Ok(subscription_id)
+    Ok(())
 }
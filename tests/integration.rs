@@ -0,0 +1,354 @@
+//! End-to-end coverage over a real `App`, with an actual `cw20-base` contract
+//! instantiated alongside this one, so the money-movement paths that unit
+//! tests over `Storage` alone can't exercise (deposits, refunds, cw20 pulls)
+//! are asserted against real bank/wasm execution.
+
+use cosmwasm_std::testing::MockApi;
+use cosmwasm_std::{
+    coin, Addr, Api, CanonicalAddr, Coin, RecoverPubkeyError, StdResult, Timestamp, Uint128,
+    VerificationError,
+};
+use cw0::Expiration;
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw_multi_test::{App, AppBuilder, ContractWrapper, Executor};
+
+use cw_subscription::contract::{execute, instantiate, query, reply};
+use cw_subscription::cron_spec::CronSpec;
+use cw_subscription::{
+    AssetInfo, BillingMode, CollectOne, ExecuteMsg, InitMsg, Params, Plan, PlanContent,
+    PlansResponse, QueryMsg, Subscription, SubscriptionsResponse, Tier,
+};
+
+const NATIVE_DENOM: &str = "ucro";
+const OPERATOR: &str = "operator";
+const MERCHANT: &str = "merchant";
+const USER: &str = "user";
+
+/// `cw-multi-test` 0.9.1 hands out contract addresses like `"Contract #0"`,
+/// which `MockApi`'s `addr_validate` rejects as "not normalized" (it
+/// requires `humanize(canonicalize(input)) == input`, and canonicalization
+/// lowercases). Delegate everything to `MockApi` except that check, so
+/// contract-to-contract calls against those addresses still work.
+struct TestApi(MockApi);
+
+impl Api for TestApi {
+    fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+        self.0.addr_canonicalize(human)?;
+        Ok(Addr::unchecked(human))
+    }
+
+    fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+        self.0.addr_canonicalize(human)
+    }
+
+    fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        self.0.addr_humanize(canonical)
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.secp256k1_verify(message_hash, signature, public_key)
+    }
+
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        self.0
+            .secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_verify(message, signature, public_key)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_batch_verify(messages, signatures, public_keys)
+    }
+
+    fn debug(&self, message: &str) {
+        self.0.debug(message)
+    }
+}
+
+fn mock_app() -> App {
+    let mut app = AppBuilder::new().with_api(TestApi(MockApi::default())).build();
+    app.init_bank_balance(&Addr::unchecked(USER), vec![coin(1_000, NATIVE_DENOM)])
+        .unwrap();
+    app
+}
+
+fn store_cw20_base(app: &mut App) -> u64 {
+    app.store_code(Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )))
+}
+
+fn store_subscription(app: &mut App) -> u64 {
+    app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_reply(reply),
+    ))
+}
+
+fn instantiate_cw20(app: &mut App, code_id: u64, initial_balance: Uint128) -> Addr {
+    let msg = Cw20InstantiateMsg {
+        name: "Test Token".to_owned(),
+        symbol: "TEST".to_owned(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: USER.to_owned(),
+            amount: initial_balance,
+        }],
+        mint: None,
+        marketing: None,
+    };
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(OPERATOR),
+        &msg,
+        &[],
+        "token",
+        None,
+    )
+    .unwrap()
+}
+
+fn instantiate_subscription(
+    app: &mut App,
+    code_id: u64,
+    required_deposit_subscription: Vec<Coin>,
+) -> Addr {
+    let msg = InitMsg {
+        params: Params {
+            required_deposit_plan: vec![],
+            required_deposit_subscription,
+            max_consecutive_failures: None,
+            prorate_on_cancel: false,
+            nft_collection: None,
+        },
+    };
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(OPERATOR),
+        &msg,
+        &[],
+        "subscription",
+        None,
+    )
+    .unwrap()
+}
+
+fn create_plan(app: &mut App, contract: &Addr, token: &Addr, amount: Uint128) -> Uint128 {
+    let content = PlanContent::<String> {
+        title: "monthly support".to_owned(),
+        description: "recurring cw20 payment".to_owned(),
+        asset: AssetInfo::Cw20 {
+            addr: token.to_string(),
+        },
+        tiers: vec![Tier {
+            tier_id: 0,
+            amount,
+        }],
+        billing_mode: BillingMode::Flat,
+        cron: "* * * * *".parse::<CronSpec>().unwrap().compile().unwrap(),
+        tzoffset: 0,
+        goal: None,
+        deadline: None,
+    };
+    app.execute_contract(
+        Addr::unchecked(MERCHANT),
+        contract.clone(),
+        &ExecuteMsg::CreatePlan(content),
+        &[],
+    )
+    .unwrap();
+
+    let plans: PlansResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract.clone(),
+            &QueryMsg::ListPlans {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    plans.plans.last().unwrap().id
+}
+
+fn cw20_balance(app: &App, token: &Addr, address: &str) -> Uint128 {
+    let rsp: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token,
+            &Cw20QueryMsg::Balance {
+                address: address.to_owned(),
+            },
+        )
+        .unwrap();
+    rsp.balance
+}
+
+#[test]
+fn collection_transfers_cw20_from_subscriber_to_merchant() {
+    let mut app = mock_app();
+    let cw20_id = store_cw20_base(&mut app);
+    let sub_id = store_subscription(&mut app);
+
+    let token = instantiate_cw20(&mut app, cw20_id, Uint128::new(1_000));
+    let contract = instantiate_subscription(&mut app, sub_id, vec![coin(50, NATIVE_DENOM)]);
+
+    let amount = Uint128::new(10);
+    let plan_id = create_plan(&mut app, &contract, &token, amount);
+
+    // the subscriber must approve the subscription contract to pull cw20
+    app.execute_contract(
+        Addr::unchecked(USER),
+        token.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance {
+            spender: contract.to_string(),
+            amount: Uint128::new(1_000),
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(USER),
+        contract.clone(),
+        &ExecuteMsg::Subscribe {
+            plan_id,
+            tier_id: 0,
+            expires: Expiration::Never {},
+        },
+        &[coin(50, NATIVE_DENOM)],
+    )
+    .unwrap();
+
+    let sub: Subscription = app
+        .wrap()
+        .query_wasm_smart(
+            &contract,
+            &QueryMsg::Subscription {
+                plan_id,
+                subscriber: USER.to_owned(),
+            },
+        )
+        .unwrap();
+
+    app.update_block(|block| {
+        block.time = Timestamp::from_seconds(sub.next_collection_time as u64)
+    });
+
+    let collectible: SubscriptionsResponse = app
+        .wrap()
+        .query_wasm_smart(&contract, &QueryMsg::CollectibleSubscriptions { limit: None })
+        .unwrap();
+    assert_eq!(collectible.subscriptions.len(), 1);
+
+    app.execute_contract(
+        Addr::unchecked(USER),
+        contract.clone(),
+        &ExecuteMsg::Collection {
+            items: vec![CollectOne {
+                plan_id,
+                subscriber: USER.to_owned(),
+                current_collection_time: sub.next_collection_time,
+                tier_id: 0,
+                usage_units: None,
+            }],
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(cw20_balance(&app, &token, USER), Uint128::new(990));
+    assert_eq!(cw20_balance(&app, &token, MERCHANT), amount);
+
+    let sub: Subscription = app
+        .wrap()
+        .query_wasm_smart(
+            &contract,
+            &QueryMsg::Subscription {
+                plan_id,
+                subscriber: USER.to_owned(),
+            },
+        )
+        .unwrap();
+    assert!(sub.next_collection_time > sub.last_collection_time);
+}
+
+#[test]
+fn stop_plan_refunds_deposit_to_merchant_and_live_subscribers() {
+    let mut app = mock_app();
+    let cw20_id = store_cw20_base(&mut app);
+    let sub_id = store_subscription(&mut app);
+
+    let token = instantiate_cw20(&mut app, cw20_id, Uint128::new(1_000));
+    let contract = instantiate_subscription(&mut app, sub_id, vec![coin(50, NATIVE_DENOM)]);
+
+    let plan_id = create_plan(&mut app, &contract, &token, Uint128::new(10));
+
+    app.execute_contract(
+        Addr::unchecked(USER),
+        contract.clone(),
+        &ExecuteMsg::Subscribe {
+            plan_id,
+            tier_id: 0,
+            expires: Expiration::Never {},
+        },
+        &[coin(50, NATIVE_DENOM)],
+    )
+    .unwrap();
+
+    assert_eq!(
+        app.wrap()
+            .query_balance(USER, NATIVE_DENOM)
+            .unwrap()
+            .amount,
+        Uint128::new(950)
+    );
+
+    app.execute_contract(
+        Addr::unchecked(MERCHANT),
+        contract.clone(),
+        &ExecuteMsg::StopPlan { plan_id },
+        &[],
+    )
+    .unwrap();
+
+    // the subscriber's deposit is refunded once the plan is stopped
+    assert_eq!(
+        app.wrap()
+            .query_balance(USER, NATIVE_DENOM)
+            .unwrap()
+            .amount,
+        Uint128::new(1_000)
+    );
+
+    // the plan itself no longer exists
+    let plan: Result<Plan, _> = app
+        .wrap()
+        .query_wasm_smart(&contract, &QueryMsg::Plan { plan_id });
+    assert!(plan.is_err());
+}